@@ -0,0 +1,31 @@
+// Минимальный локальный HTTP-сервер для замера пропускной способности load_test.
+// Отвечает 200 OK с коротким body на любой запрос.
+//
+// cargo run --release --example local_server -- 3000
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+
+async fn handle(_request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    Ok(Response::new(Body::from("ok")))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let port = std::env::args()
+        .nth(1)
+        .map(|port| port.parse())
+        .transpose()?
+        .unwrap_or(3000);
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+
+    let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+
+    println!("🖥️  Локальный сервер слушает http://{}", addr);
+    Server::bind(&addr).serve(make_service).await?;
+
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use base64::{Engine as _, engine::general_purpose};
+use load_test::AuthConfig;
+use reqwest::Client;
+use reqwest::header::HeaderValue;
+use serde::Deserialize;
+
+// Доля срока жизни токена OAuth2, после которой он обновляется
+const REFRESH_AT: f64 = 0.8;
+// Пауза перед повторной попыткой обновить токен
+const REFRESH_RETRY: Duration = Duration::from_secs(5);
+// Таймаут запроса к серверу токенов: зависший сервер не должен останавливать обновление
+const TOKEN_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Как часто проверять изменение файла с токеном
+const TOKEN_FILE_CHECK: Duration = Duration::from_secs(1);
+
+// Значение заголовка Authorization, общее для всех виртуальных пользователей.
+// Для токена из файла и OAuth2 обновляется фоновой задачей.
+#[derive(Debug, Clone)]
+pub(crate) struct Authorization {
+    value: Arc<RwLock<HeaderValue>>,
+}
+
+impl Authorization {
+    fn new(value: HeaderValue) -> Self {
+        Self {
+            value: Arc::new(RwLock::new(value)),
+        }
+    }
+
+    pub(crate) fn header(&self) -> HeaderValue {
+        self.value.read().unwrap().clone()
+    }
+
+    fn set(&self, value: HeaderValue) {
+        *self.value.write().unwrap() = value;
+    }
+}
+
+// Готовит авторизацию для URL теста; одинаковые настройки используют общий токен
+pub(crate) struct AuthProviders {
+    client: Client,
+    prepared: HashMap<AuthConfig, Authorization>,
+}
+
+impl AuthProviders {
+    pub(crate) fn new(client: Client) -> Self {
+        Self {
+            client,
+            prepared: HashMap::new(),
+        }
+    }
+
+    pub(crate) async fn get(&mut self, config: &AuthConfig) -> Result<Authorization, String> {
+        if let Some(authorization) = self.prepared.get(config) {
+            return Ok(authorization.clone());
+        }
+
+        let authorization = match config {
+            AuthConfig::Basic { username, password } => {
+                let credentials = format!("{}:{}", username, password.as_deref().unwrap_or(""));
+                Authorization::new(header_value(&format!(
+                    "Basic {}",
+                    general_purpose::STANDARD.encode(credentials)
+                ))?)
+            }
+            AuthConfig::Bearer { token_file: Some(path), token_env: None } => {
+                let (token, modified) = read_token_file(path)?;
+                let authorization = Authorization::new(bearer(&token)?);
+                spawn_token_file_watcher(authorization.clone(), path.clone(), modified);
+                authorization
+            }
+            AuthConfig::Bearer { token_file: None, token_env: Some(name) } => {
+                let token = std::env::var(name)
+                    .map_err(|_| format!("Переменная окружения {} с токеном не задана", name))?;
+                Authorization::new(bearer(token.trim())?)
+            }
+            AuthConfig::Bearer { .. } => {
+                return Err("Для bearer укажите ровно одно из token_file и token_env".to_string());
+            }
+            AuthConfig::OAuth2 { token_url, .. } => {
+                let token = fetch_token(&self.client, config).await?;
+                match token.expires_in {
+                    Some(lifetime) => println!("🔑 Токен OAuth2 получен от {}, действует {} с", token_url, lifetime),
+                    None => println!("🔑 Токен OAuth2 получен от {}", token_url),
+                }
+                let authorization = Authorization::new(bearer(&token.access_token)?);
+                if let Some(lifetime) = token.expires_in {
+                    spawn_token_refresher(authorization.clone(), self.client.clone(), config.clone(), lifetime);
+                }
+                authorization
+            }
+        };
+
+        self.prepared.insert(config.clone(), authorization.clone());
+        Ok(authorization)
+    }
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, String> {
+    let mut value = HeaderValue::from_str(value)
+        .map_err(|e| format!("Некорректное значение для Authorization: {}", e))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+fn bearer(token: &str) -> Result<HeaderValue, String> {
+    if token.is_empty() {
+        return Err("Пустой bearer-токен".to_string());
+    }
+    header_value(&format!("Bearer {}", token))
+}
+
+fn read_token_file(path: &str) -> Result<(String, Option<SystemTime>), String> {
+    let token = std::fs::read_to_string(path)
+        .map_err(|e| format!("Не удалось прочитать токен из {}: {}", path, e))?;
+    let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    Ok((token.trim().to_string(), modified))
+}
+
+// Перечитывает файл с токеном, когда его обновляет внешний процесс
+fn spawn_token_file_watcher(authorization: Authorization, path: String, mut modified: Option<SystemTime>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(TOKEN_FILE_CHECK);
+        loop {
+            ticker.tick().await;
+            let current = std::fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+            if current.is_none() || current == modified {
+                continue;
+            }
+
+            match read_token_file(&path).and_then(|(token, _)| bearer(&token)) {
+                Ok(value) => {
+                    authorization.set(value);
+                    modified = current;
+                }
+                Err(e) => eprintln!("⚠️  Токен не обновлен: {}", e),
+            }
+        }
+    });
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    // Срок жизни токена в секундах
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+// Запрос токена по схеме client credentials; клиент передается через Basic (RFC 6749, 2.3.1)
+async fn fetch_token(client: &Client, config: &AuthConfig) -> Result<TokenResponse, String> {
+    let AuthConfig::OAuth2 { token_url, client_id, client_secret, scope, audience } = config else {
+        return Err("Ожидаются настройки OAuth2".to_string());
+    };
+
+    let mut form = vec![("grant_type", "client_credentials")];
+    if let Some(scope) = scope {
+        form.push(("scope", scope));
+    }
+    if let Some(audience) = audience {
+        form.push(("audience", audience));
+    }
+
+    let response = client
+        .post(token_url)
+        .basic_auth(client_id, Some(client_secret))
+        .form(&form)
+        .timeout(TOKEN_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Не удалось получить токен OAuth2 от {}: {}", token_url, e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Сервер токенов {} ответил HTTP {}: {}", token_url, status, body.trim()));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Некорректный ответ сервера токенов {}: {}", token_url, e))
+}
+
+// Обновляет токен OAuth2 до истечения; при ошибке оставляет прежний и повторяет попытку
+fn spawn_token_refresher(authorization: Authorization, client: Client, config: AuthConfig, lifetime: u64) {
+    tokio::spawn(async move {
+        let mut delay = refresh_delay(lifetime);
+        loop {
+            tokio::time::sleep(delay).await;
+
+            match fetch_token(&client, &config).await.and_then(|token| {
+                let value = bearer(&token.access_token)?;
+                Ok((value, token.expires_in))
+            }) {
+                Ok((value, expires_in)) => {
+                    authorization.set(value);
+                    let Some(lifetime) = expires_in else {
+                        eprintln!("⚠️  Сервер токенов не указал expires_in: токен OAuth2 больше не обновляется");
+                        return;
+                    };
+                    delay = refresh_delay(lifetime);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "⚠️  Токен OAuth2 не обновлен, запросы идут с прежним: {}; повтор через {} с",
+                        e,
+                        REFRESH_RETRY.as_secs()
+                    );
+                    delay = REFRESH_RETRY;
+                }
+            }
+        }
+    });
+}
+
+fn refresh_delay(lifetime: u64) -> Duration {
+    Duration::from_secs(lifetime).mul_f64(REFRESH_AT).max(Duration::from_secs(1))
+}
+
+// Краткое описание для заголовка теста
+pub(crate) fn describe(config: &AuthConfig) -> String {
+    match config {
+        AuthConfig::Basic { username, .. } => format!("Basic ({})", username),
+        AuthConfig::Bearer { token_file: Some(path), .. } => format!("Bearer из файла {}", path),
+        AuthConfig::Bearer { token_env: Some(name), .. } => format!("Bearer из ${}", name),
+        AuthConfig::Bearer { .. } => "Bearer".to_string(),
+        AuthConfig::OAuth2 { token_url, .. } => format!("OAuth2 ({})", token_url),
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+// Окно по умолчанию, если в условии не указано "for <длительность>"
+pub const DEFAULT_ABORT_WINDOW: Duration = Duration::from_secs(5);
+
+// Метрика, по которой тест останавливается досрочно
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbortMetric {
+    // Доля неудачных запросов в процентах
+    ErrorRate(f64),
+    // Перцентиль времени ответа (например 99.0 для p99)
+    Latency { percentile: f64, limit: Duration },
+}
+
+// Условие досрочной остановки, например "error_rate>20% for 10s" или "p99>5s".
+// Метрика считается по скользящему окну последних `window` секунд.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbortCondition {
+    pub metric: AbortMetric,
+    pub window: Duration,
+    source: String,
+}
+
+impl FromStr for AbortCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source = s.trim().to_string();
+
+        let (expression, window) = match source.split_once(" for ") {
+            Some((expression, window)) => (expression.trim(), parse_duration(window)?),
+            None => (source.as_str(), DEFAULT_ABORT_WINDOW),
+        };
+        if window.is_zero() {
+            return Err(format!("Окно условия должно быть больше нуля: {}", source));
+        }
+
+        let (metric, threshold) = expression
+            .split_once('>')
+            .map(|(metric, threshold)| (metric.trim(), threshold.trim()))
+            .ok_or_else(|| format!("Ожидается условие вида metric>value: {}", source))?;
+
+        let metric = if metric == "error_rate" {
+            let percent = threshold.strip_suffix('%').unwrap_or(threshold).trim();
+            let percent: f64 = percent
+                .parse()
+                .map_err(|_| format!("Некорректный процент ошибок: {}", threshold))?;
+            AbortMetric::ErrorRate(percent)
+        } else if let Some(percentile) = metric.strip_prefix('p') {
+            let percentile: f64 = percentile
+                .parse()
+                .ok()
+                .filter(|p| *p > 0.0 && *p <= 100.0)
+                .ok_or_else(|| format!("Некорректный перцентиль: {}", metric))?;
+            AbortMetric::Latency {
+                percentile,
+                limit: parse_duration(threshold)?,
+            }
+        } else {
+            return Err(format!(
+                "Неизвестная метрика {}: поддерживаются error_rate и p50/p90/p95/p99",
+                metric
+            ));
+        };
+
+        Ok(Self {
+            metric,
+            window,
+            source,
+        })
+    }
+}
+
+impl fmt::Display for AbortCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+// Длительность в формате "500ms", "5s", "2m" или "1h". Единица обязательна:
+// в "p99>5" нельзя угадать, имелись в виду секунды или миллисекунды
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, multiplier) = if let Some(number) = s.strip_suffix("ms") {
+        (number, 0.001)
+    } else if let Some(number) = s.strip_suffix('s') {
+        (number, 1.0)
+    } else if let Some(number) = s.strip_suffix('m') {
+        (number, 60.0)
+    } else if let Some(number) = s.strip_suffix('h') {
+        (number, 3600.0)
+    } else {
+        return Err(format!("Некорректная длительность {}: нужна единица ms, s, m или h", s));
+    };
+
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .and_then(|value| Duration::try_from_secs_f64(value * multiplier).ok())
+        .ok_or_else(|| format!("Некорректная длительность: {}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_rate_with_window() {
+        let condition: AbortCondition = "error_rate>20% for 10s".parse().unwrap();
+        assert_eq!(condition.metric, AbortMetric::ErrorRate(20.0));
+        assert_eq!(condition.window, Duration::from_secs(10));
+        assert_eq!(condition.to_string(), "error_rate>20% for 10s");
+    }
+
+    #[test]
+    fn latency_with_default_window() {
+        let condition: AbortCondition = " p99 > 5s ".parse().unwrap();
+        assert_eq!(
+            condition.metric,
+            AbortMetric::Latency { percentile: 99.0, limit: Duration::from_secs(5) }
+        );
+        assert_eq!(condition.window, DEFAULT_ABORT_WINDOW);
+
+        let condition: AbortCondition = "p99.9>250ms for 1m".parse().unwrap();
+        assert_eq!(
+            condition.metric,
+            AbortMetric::Latency { percentile: 99.9, limit: Duration::from_millis(250) }
+        );
+        assert_eq!(condition.window, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn malformed_conditions() {
+        for (input, message) in [
+            ("p99>5", "нужна единица"),
+            ("error_rate>20% for 10", "нужна единица"),
+            ("p99>5%", "нужна единица"),
+            ("p99>5s%", "нужна единица"),
+            ("p99<5s", "metric>value"),
+            ("p99>=5s", "Некорректная длительность"),
+            ("error_rate>=20%", "Некорректный процент"),
+            ("error_rate>abc%", "Некорректный процент"),
+            ("p0>5s", "Некорректный перцентиль"),
+            ("p101>5s", "Некорректный перцентиль"),
+            ("latency>5s", "Неизвестная метрика"),
+            ("p99>5s for 0s", "больше нуля"),
+            ("p99>-1s", "Некорректная длительность"),
+            ("p99>1e300h", "Некорректная длительность"),
+        ] {
+            let error = input.parse::<AbortCondition>().unwrap_err();
+            assert!(error.contains(message), "{}: {}", input, error);
+        }
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration(" 1h "), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("5").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("NaNs").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+// Способ авторизации: задается в командной строке или в поле `auth` у URL в файле multi
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthConfig {
+    Basic {
+        username: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+    },
+    // Токен из файла перечитывается при изменении файла
+    Bearer {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_file: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_env: Option<String>,
+    },
+    // OAuth2 client credentials: токен запрашивается до начала теста и обновляется до истечения
+    #[serde(rename = "oauth2")]
+    OAuth2 {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scope: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        audience: Option<String>,
+    },
+}
+
+// Авторизация из командной строки; в режиме multi действует для URL без своего поля `auth`
+#[derive(Args, Debug, Clone, Default)]
+pub struct AuthOptions {
+    /// Basic-авторизация в формате user:password
+    #[arg(long, conflicts_with_all = ["bearer_file", "bearer_env", "oauth2_token_url"])]
+    pub basic_auth: Option<String>,
+
+    /// Bearer-токен из файла (перечитывается при изменении файла)
+    #[arg(long, conflicts_with_all = ["bearer_env", "oauth2_token_url"])]
+    pub bearer_file: Option<String>,
+
+    /// Bearer-токен из переменной окружения
+    #[arg(long, conflicts_with = "oauth2_token_url")]
+    pub bearer_env: Option<String>,
+
+    /// Адрес выдачи токенов OAuth2 (client credentials)
+    #[arg(long, requires_all = ["oauth2_client_id", "oauth2_client_secret"])]
+    pub oauth2_token_url: Option<String>,
+
+    /// client_id для OAuth2
+    #[arg(long, requires = "oauth2_token_url")]
+    pub oauth2_client_id: Option<String>,
+
+    /// client_secret для OAuth2
+    #[arg(long, requires = "oauth2_token_url")]
+    pub oauth2_client_secret: Option<String>,
+
+    /// scope для OAuth2
+    #[arg(long, requires = "oauth2_token_url")]
+    pub oauth2_scope: Option<String>,
+
+    /// audience для OAuth2
+    #[arg(long, requires = "oauth2_token_url")]
+    pub oauth2_audience: Option<String>,
+}
+
+impl AuthOptions {
+    pub fn config(&self) -> Option<AuthConfig> {
+        if let Some(credentials) = &self.basic_auth {
+            let (username, password) = match credentials.split_once(':') {
+                Some((username, password)) => (username, Some(password.to_string())),
+                None => (credentials.as_str(), None),
+            };
+            return Some(AuthConfig::Basic {
+                username: username.to_string(),
+                password,
+            });
+        }
+
+        if self.bearer_file.is_some() || self.bearer_env.is_some() {
+            return Some(AuthConfig::Bearer {
+                token_file: self.bearer_file.clone(),
+                token_env: self.bearer_env.clone(),
+            });
+        }
+
+        // client_id и client_secret обязательны вместе с --oauth2-token-url (проверяет clap)
+        match (&self.oauth2_token_url, &self.oauth2_client_id, &self.oauth2_client_secret) {
+            (Some(token_url), Some(client_id), Some(client_secret)) => Some(AuthConfig::OAuth2 {
+                token_url: token_url.clone(),
+                client_id: client_id.clone(),
+                client_secret: client_secret.clone(),
+                scope: self.oauth2_scope.clone(),
+                audience: self.oauth2_audience.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use clap::{ValueEnum};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, ValueEnum, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum HttpMethod {
     GET,
@@ -26,4 +26,15 @@ impl From<HttpMethod> for Method {
             HttpMethod::OPTIONS => Method::OPTIONS,
         }
     }
-}
\ No newline at end of file
+}
+
+// Как читать строку body: auto угадывает JSON, форму, base64 или текст,
+// text отправляет строку как есть, base64 - декодированные байты
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyKind {
+    #[default]
+    Auto,
+    Text,
+    Base64,
+}
@@ -0,0 +1,178 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use clap::{ArgAction, Args};
+
+use super::auth::AuthOptions;
+use super::signing::SigningOptions;
+use super::tls::TlsOptions;
+
+// Как в reqwest и браузерах
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+// --no-proxy без списка хостов: прокси не используются совсем
+pub const NO_PROXY_ALL: &str = "*";
+
+// Подмена DNS в стиле curl: host:port:ip[,ip...]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveOverride {
+    pub host: String,
+    pub port: u16,
+    pub addrs: Vec<IpAddr>,
+}
+
+impl FromStr for ResolveOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+        let (Some(host), Some(port), Some(addrs)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("Ожидается host:port:ip[,ip...]: {}", s));
+        };
+
+        let port = port
+            .parse()
+            .map_err(|_| format!("Некорректный порт в {}", s))?;
+        let addrs = addrs
+            .split(',')
+            .map(|addr| {
+                let addr = addr.trim().trim_start_matches('[').trim_end_matches(']');
+                addr.parse::<IpAddr>()
+                    .map_err(|_| format!("Некорректный IP-адрес {} в {}", addr, s))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if host.is_empty() {
+            return Err(format!("Не указан хост в {}", s));
+        }
+
+        Ok(Self {
+            host: host.to_lowercase(),
+            port,
+            addrs,
+        })
+    }
+}
+
+// Настройки HTTP-клиента, общие для burst, rps и multi
+#[derive(Args, Debug, Clone)]
+pub struct ClientOptions {
+    /// HTTP/2 без согласования версии (prior knowledge, в том числе h2c)
+    #[arg(long, conflicts_with = "http1_only")]
+    pub http2_prior_knowledge: bool,
+
+    /// Только HTTP/1.1
+    #[arg(long)]
+    pub http1_only: bool,
+
+    /// Новое соединение на каждый запрос (без keep-alive)
+    #[arg(long, conflicts_with = "pool_max_idle")]
+    pub no_keepalive: bool,
+
+    /// Максимум простаивающих соединений на хост в пуле
+    #[arg(long)]
+    pub pool_max_idle: Option<usize>,
+
+    /// Сколько секунд простаивающее соединение живет в пуле
+    #[arg(long)]
+    pub pool_idle_timeout: Option<u64>,
+
+    /// TCP_NODELAY для исходящих соединений
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub tcp_nodelay: bool,
+
+    /// Таймаут установки соединения в секундах (не зависит от таймаута запроса -t)
+    #[arg(long)]
+    pub connect_timeout: Option<u64>,
+
+    /// Максимум редиректов на запрос; при превышении запрос считается неудачным
+    #[arg(long, default_value_t = DEFAULT_MAX_REDIRECTS, conflicts_with = "no_follow")]
+    pub max_redirects: usize,
+
+    /// Не следовать редиректам: ответ 3xx считается успешным
+    #[arg(long)]
+    pub no_follow: bool,
+
+    /// Направить host:port на указанные IP (как в curl, можно указать несколько);
+    /// несколько адресов используются по очереди для новых соединений
+    #[arg(long)]
+    pub resolve: Vec<ResolveOverride>,
+
+    /// Разрешить имена один раз и распределять новые соединения по всем A/AAAA записям
+    #[arg(long)]
+    pub dns_spread: bool,
+
+    /// Прокси для всех запросов: http://, https://, socks5:// или socks5h://
+    #[arg(long)]
+    pub proxy: Option<String>,
+
+    /// Хосты через запятую, которые идут мимо --proxy; без значения - не использовать
+    /// никакие прокси, в том числе из переменных окружения
+    #[arg(long, num_args = 0..=1, default_missing_value = NO_PROXY_ALL)]
+    pub no_proxy: Option<String>,
+
+    /// Локальный адрес для исходящих соединений (можно указать несколько):
+    /// воркеры распределяются по адресам по очереди
+    #[arg(long)]
+    pub local_address: Vec<IpAddr>,
+
+    #[command(flatten)]
+    pub tls: TlsOptions,
+
+    #[command(flatten)]
+    pub auth: AuthOptions,
+
+    #[command(flatten)]
+    pub signing: SigningOptions,
+}
+
+impl ClientOptions {
+    // Сколько редиректов проходить; None - не следовать редиректам
+    pub fn redirect_limit(&self) -> Option<usize> {
+        (!self.no_follow).then_some(self.max_redirects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_override_with_several_addresses() {
+        let entry: ResolveOverride = "API.example.com:443:10.0.0.1, 10.0.0.2".parse().unwrap();
+        assert_eq!(entry, ResolveOverride {
+            host: "api.example.com".to_string(),
+            port: 443,
+            addrs: vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()],
+        });
+    }
+
+    #[test]
+    fn resolve_override_with_ipv6_in_brackets() {
+        let entry: ResolveOverride = "example.com:8443:[::1],[2001:db8::1],127.0.0.1".parse().unwrap();
+        assert_eq!(entry.port, 8443);
+        let addrs: Vec<String> = entry.addrs.iter().map(IpAddr::to_string).collect();
+        assert_eq!(addrs, ["::1", "2001:db8::1", "127.0.0.1"]);
+
+        // Без скобок тоже: адреса идут после второго двоеточия
+        let entry: ResolveOverride = "example.com:80:::1".parse().unwrap();
+        assert_eq!(entry.addrs, ["::1".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn malformed_resolve_overrides() {
+        for (input, message) in [
+            ("example.com:443", "host:port:ip"),
+            ("example.com", "host:port:ip"),
+            ("example.com:https:10.0.0.1", "Некорректный порт"),
+            ("example.com:70000:10.0.0.1", "Некорректный порт"),
+            ("example.com:443:10.0.0.300", "Некорректный IP-адрес"),
+            ("example.com:443:", "Некорректный IP-адрес"),
+            ("example.com:443:10.0.0.1,", "Некорректный IP-адрес"),
+            (":443:10.0.0.1", "Не указан хост"),
+        ] {
+            let error = input.parse::<ResolveOverride>().unwrap_err();
+            assert!(error.contains(message), "{}: {}", input, error);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// GraphQL-запрос: задается в командной строке или в поле `graphql` у URL в файле multi
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GraphqlConfig {
+    // Файл с документом GraphQL (query или mutation)
+    pub query_file: String,
+    // Имя операции; если не указано, берется из документа с единственной операцией
+    #[serde(default)]
+    pub operation_name: Option<String>,
+    // Переменные; строки {{userId}}, {{timestamp}}, {{uuid}} подставляются с --dynamic-body
+    #[serde(default)]
+    pub variables: Option<Value>,
+}
+
+impl GraphqlConfig {
+    pub fn load_query(&self) -> Result<String, String> {
+        std::fs::read_to_string(&self.query_file)
+            .map_err(|e| format!("Не удалось прочитать {}: {}", self.query_file, e))
+    }
+}
+
+// GraphQL из командной строки; в режиме multi действует для URL без своего поля `graphql`
+#[derive(Args, Debug, Clone, Default)]
+pub struct GraphqlOptions {
+    /// Файл с запросом GraphQL; body собирается из запроса, операции и переменных
+    #[arg(long, conflicts_with = "body")]
+    pub graphql_query: Option<String>,
+
+    /// Имя операции GraphQL (обязательно, если в файле несколько операций)
+    #[arg(long, requires = "graphql_query")]
+    pub operation_name: Option<String>,
+
+    /// Переменные GraphQL в JSON, например '{"id":"{{userId}}"}'
+    #[arg(long, requires = "graphql_query")]
+    pub variables: Option<String>,
+}
+
+impl GraphqlOptions {
+    pub fn config(&self) -> Result<Option<GraphqlConfig>, String> {
+        let Some(query_file) = &self.graphql_query else {
+            return Ok(None);
+        };
+        let variables = self.variables.as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| format!("Некорректные переменные GraphQL: {}", e))?;
+        Ok(Some(GraphqlConfig {
+            query_file: query_file.clone(),
+            operation_name: self.operation_name.clone(),
+            variables,
+        }))
+    }
+}
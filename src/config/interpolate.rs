@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use serde_json::Value;
+
+// Значения из ${secret:файл} и переменных с именами из SENSITIVE_NAMES: в check и отчетах выводятся как ***
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// Источники секретов короче MIN_SECRET_LEN: их значения не скрываются
+static SHORT_SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// Короткое значение (1, dev) встречается в выводе и помимо секрета: замена на *** испортила бы отчет
+pub const MIN_SECRET_LEN: usize = 6;
+
+// Части имен переменных, значения которых скрываются. Остальные ${VAR} выводятся как есть:
+// по значению вроде 1 или staging нельзя отличить секрет, а замена испортила бы весь вывод
+const SENSITIVE_NAMES: [&str; 5] = ["TOKEN", "SECRET", "PASSWORD", "PASSWD", "API_KEY"];
+
+// Переменные для подстановки ${VAR} в файле multi: окружение процесса, затем файл .env
+#[derive(Debug, Default)]
+pub struct ConfigEnv {
+    file: HashMap<String, String>,
+}
+
+impl ConfigEnv {
+    // Файл .env: строки ИМЯ=значение, # - комментарий, необязательный export и кавычки вокруг значения
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?;
+        let mut file = HashMap::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_once('=')
+                .ok_or_else(|| format!("{}:{}: ожидается ИМЯ=значение", path, index + 1))?;
+            file.insert(name.trim().to_string(), unquote(value.trim()).to_string());
+        }
+        Ok(Self { file })
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok().or_else(|| self.file.get(name).cloned())
+    }
+
+    // Подставляет ${VAR}, ${VAR:-по умолчанию} и ${secret:файл}; $$ - символ $
+    pub fn interpolate(&self, text: &str) -> Result<String, String> {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(index) = rest.find('$') {
+            result.push_str(&rest[..index]);
+            rest = &rest[index + 1..];
+            if let Some(after) = rest.strip_prefix('$') {
+                result.push('$');
+                rest = after;
+                continue;
+            }
+            let Some(after) = rest.strip_prefix('{') else {
+                result.push('$');
+                continue;
+            };
+            let end = after.find('}')
+                .ok_or_else(|| format!("Незакрытая подстановка ${{{} в \"{}\"", after, text))?;
+            result.push_str(&self.resolve(&after[..end])?);
+            rest = &after[end + 1..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    fn resolve(&self, expression: &str) -> Result<String, String> {
+        if let Some(path) = expression.strip_prefix("secret:") {
+            let secret = std::fs::read_to_string(path.trim())
+                .map_err(|e| format!("Не удалось прочитать секрет {}: {}", path.trim(), e))?;
+            let secret = secret.trim_end_matches(['\r', '\n']).to_string();
+            register_secret(&format!("${{{}}}", expression), &secret);
+            return Ok(secret);
+        }
+
+        let (name, default) = match expression.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expression, None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("Некорректное имя переменной в ${{{}}}", expression));
+        }
+        // Как в sh: с :- пустая переменная тоже заменяется значением по умолчанию
+        let value = match (self.get(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => default.to_string(),
+            (Some(value), _) => value,
+            (None, Some(default)) => default.to_string(),
+            (None, None) => return Err(format!("Переменная {} не задана (окружение и --env-file)", name)),
+        };
+        let upper = name.to_ascii_uppercase();
+        if SENSITIVE_NAMES.iter().any(|part| upper.contains(part)) {
+            register_secret(name, &value);
+        }
+        Ok(value)
+    }
+
+    // Подставляет переменные во все строки документа
+    pub fn interpolate_value(&self, value: Value) -> Result<Value, String> {
+        Ok(match value {
+            Value::String(text) => Value::String(self.interpolate(&text)?),
+            Value::Array(items) => Value::Array(
+                items.into_iter().map(|item| self.interpolate_value(item)).collect::<Result<_, _>>()?,
+            ),
+            Value::Object(fields) => Value::Object(
+                fields.into_iter()
+                    .map(|(key, value)| Ok((key, self.interpolate_value(value)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            value => value,
+        })
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    // Без кавычек " #" начинает комментарий
+    value.split(" #").next().unwrap_or(value).trim_end()
+}
+
+fn register_secret(source: &str, secret: &str) {
+    if secret.is_empty() {
+        return;
+    }
+    if secret.chars().count() < MIN_SECRET_LEN {
+        let mut short = SHORT_SECRETS.write().unwrap_or_else(|e| e.into_inner());
+        if !short.iter().any(|known| known == source) {
+            short.push(source.to_string());
+        }
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap_or_else(|e| e.into_inner());
+    if !secrets.iter().any(|known| known == secret) {
+        secrets.push(secret.to_string());
+        // Длинные первыми, чтобы секрет, содержащий другой, скрывался целиком
+        secrets.sort_by_key(|known| std::cmp::Reverse(known.len()));
+    }
+}
+
+// Заменяет значения секретов на ***
+pub fn mask_secrets(text: &str) -> String {
+    let secrets = SECRETS.read().unwrap_or_else(|e| e.into_inner());
+    secrets.iter().fold(text.to_string(), |text, secret| text.replace(secret.as_str(), "***"))
+}
+
+// Секреты, которые не скрываются из-за длины: переменные и ${secret:...} для предупреждения
+pub fn unmasked_secrets() -> Vec<String> {
+    SHORT_SECRETS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// Делает относительные пути ${secret:путь} в строках документа путями от каталога base_dir.
+// Вызывается для каждого файла до слияния extends: после него не известно, откуда пришла строка.
+pub fn anchor_secret_paths(value: Value, base_dir: &Path) -> Value {
+    match value {
+        Value::String(text) => Value::String(anchor_text(&text, base_dir)),
+        Value::Array(items) => Value::Array(items.into_iter().map(|item| anchor_secret_paths(item, base_dir)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields.into_iter().map(|(key, value)| (key, anchor_secret_paths(value, base_dir))).collect(),
+        ),
+        value => value,
+    }
+}
+
+pub(crate) fn anchor_text(text: &str, base_dir: &Path) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            result.push_str("$$");
+            rest = after;
+            continue;
+        }
+        // Незакрытую подстановку оставляет как есть: об ошибке сообщит interpolate
+        if let Some(after) = rest.strip_prefix("${secret:")
+            && let Some(end) = after.find('}')
+        {
+            let path = after[..end].trim();
+            let path = if Path::new(path).is_relative() {
+                base_dir.join(path).display().to_string()
+            } else {
+                path.to_string()
+            };
+            result.push_str(&format!("${{secret:{}}}", path));
+            rest = &after[end + 1..];
+            continue;
+        }
+        result.push('$');
+        rest = &rest[1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> ConfigEnv {
+        ConfigEnv {
+            file: vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+        }
+    }
+
+    // Файл .env во временном каталоге; имя уникально для теста
+    fn env_file(name: &str, content: &str) -> ConfigEnv {
+        let path = std::env::temp_dir().join(format!("load_test_{}_{}.env", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        let env = ConfigEnv::from_file(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        env.unwrap()
+    }
+
+    #[test]
+    fn substitutes_variables_and_escapes_dollar() {
+        let env = env(&[("LOAD_TEST_HOST", "api.local")]);
+        assert_eq!(
+            env.interpolate("https://${LOAD_TEST_HOST}/price?$$=5&cur=$").unwrap(),
+            "https://api.local/price?$=5&cur=$",
+        );
+        assert_eq!(env.interpolate("$${LOAD_TEST_HOST}").unwrap(), "${LOAD_TEST_HOST}");
+    }
+
+    #[test]
+    fn default_replaces_unset_and_empty_variables() {
+        let env = env(&[("LOAD_TEST_SET", "prod"), ("LOAD_TEST_EMPTY", "")]);
+        assert_eq!(env.interpolate("${LOAD_TEST_SET:-staging}").unwrap(), "prod");
+        assert_eq!(env.interpolate("${LOAD_TEST_EMPTY:-staging}").unwrap(), "staging");
+        assert_eq!(env.interpolate("${LOAD_TEST_UNSET:-staging}").unwrap(), "staging");
+        assert_eq!(env.interpolate("${LOAD_TEST_UNSET:-}").unwrap(), "");
+        assert_eq!(env.interpolate("${LOAD_TEST_EMPTY}").unwrap(), "");
+    }
+
+    #[test]
+    fn rejects_unset_variable_and_malformed_expressions() {
+        let env = ConfigEnv::default();
+        assert!(env.interpolate("${LOAD_TEST_UNSET}").unwrap_err().contains("LOAD_TEST_UNSET не задана"));
+        assert!(env.interpolate("Bearer ${TOKEN").unwrap_err().starts_with("Незакрытая подстановка"));
+        assert!(env.interpolate("${}").unwrap_err().starts_with("Некорректное имя"));
+        assert!(env.interpolate("${LOAD-TEST}").unwrap_err().starts_with("Некорректное имя"));
+    }
+
+    #[test]
+    fn env_file_handles_quotes_comments_and_export() {
+        let env = env_file("quotes", "\
+# комментарий
+export LOAD_TEST_EXPORTED=1
+
+LOAD_TEST_DOUBLE=\"a # b\"
+LOAD_TEST_SINGLE='c=d'
+LOAD_TEST_PLAIN = plain value # комментарий
+LOAD_TEST_HASH=x#y
+");
+        assert_eq!(env.get("LOAD_TEST_EXPORTED").as_deref(), Some("1"));
+        assert_eq!(env.get("LOAD_TEST_DOUBLE").as_deref(), Some("a # b"));
+        assert_eq!(env.get("LOAD_TEST_SINGLE").as_deref(), Some("c=d"));
+        assert_eq!(env.get("LOAD_TEST_PLAIN").as_deref(), Some("plain value"));
+        assert_eq!(env.get("LOAD_TEST_HASH").as_deref(), Some("x#y"));
+    }
+
+    #[test]
+    fn env_file_rejects_line_without_assignment() {
+        let path = std::env::temp_dir().join(format!("load_test_{}_invalid.env", std::process::id()));
+        std::fs::write(&path, "LOAD_TEST_OK=1\nLOAD_TEST_BROKEN\n").unwrap();
+        let error = ConfigEnv::from_file(path.to_str().unwrap()).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.ends_with(":2: ожидается ИМЯ=значение"), "{}", error);
+    }
+
+    #[test]
+    fn secret_file_is_trimmed_and_masked() {
+        let path = std::env::temp_dir().join(format!("load_test_{}_secret.txt", std::process::id()));
+        std::fs::write(&path, "s3cr3t-51d0\r\n").unwrap();
+        let text = ConfigEnv::default().interpolate(&format!("key=${{secret:{}}}", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        let text = text.unwrap();
+        assert_eq!(text, "key=s3cr3t-51d0");
+        assert_eq!(mask_secrets(&text), "key=***");
+    }
+
+    #[test]
+    fn short_secrets_are_reported_instead_of_masked() {
+        let env = env(&[("LOAD_TEST_SHORT_TOKEN", "dev")]);
+        let text = env.interpolate("env=${LOAD_TEST_SHORT_TOKEN} developer").unwrap();
+        assert_eq!(mask_secrets(&text), "env=dev developer");
+        assert!(unmasked_secrets().contains(&"LOAD_TEST_SHORT_TOKEN".to_string()));
+    }
+
+    #[test]
+    fn relative_secret_paths_are_anchored_to_base_dir() {
+        let base = Path::new("/etc/load_test");
+        assert_eq!(
+            anchor_text("Bearer ${secret:secrets/token.txt}", base),
+            format!("Bearer ${{secret:{}}}", base.join("secrets/token.txt").display()),
+        );
+        assert_eq!(anchor_text("${secret:/run/secrets/token}", base), "${secret:/run/secrets/token}");
+        // $$ и обычные переменные не меняются, незакрытая подстановка остается для ошибки interpolate
+        assert_eq!(anchor_text("$${secret:a} ${HOST} $5", base), "$${secret:a} ${HOST} $5");
+        assert_eq!(anchor_text("${secret:a", base), "${secret:a");
+    }
+
+    #[test]
+    fn masks_values_of_sensitive_variables_only() {
+        let env = env(&[
+            ("LOAD_TEST_API_TOKEN", "tok-3f9a1c"),
+            ("load_test_db_password", "pw-77e2b0"),
+            ("LOAD_TEST_REGION", "eu-west-9"),
+        ]);
+        let text = env.interpolate(
+            "Bearer ${LOAD_TEST_API_TOKEN} ${load_test_db_password} ${LOAD_TEST_REGION}",
+        ).unwrap();
+        assert_eq!(mask_secrets(&text), "Bearer *** *** eu-west-9");
+    }
+}
@@ -1,5 +1,21 @@
 pub mod multi;
 pub mod cli;
+pub mod abort;
+pub mod client;
+pub mod tls;
+pub mod auth;
+pub mod signing;
+pub mod graphql;
+pub mod stream;
+pub mod interpolate;
 
 pub use multi::*;
-pub use cli::*;
\ No newline at end of file
+pub use cli::*;
+pub use abort::*;
+pub use client::*;
+pub use tls::*;
+pub use auth::*;
+pub use signing::*;
+pub use graphql::*;
+pub use stream::*;
+pub use interpolate::*;
\ No newline at end of file
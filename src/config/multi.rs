@@ -1,53 +1,483 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-use super::cli::HttpMethod;
+use super::auth::AuthConfig;
+use super::cli::{BodyKind, HttpMethod};
+use super::graphql::GraphqlConfig;
+use super::interpolate::{anchor_secret_paths, anchor_text, ConfigEnv};
+use super::signing::SigningConfig;
+use super::stream::StreamConfig;
+use super::tls::TlsOptions;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct UrlConfig {
     pub url: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub method: Option<HttpMethod>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub body: Option<String>,
-    #[serde(default)]
+    // Как читать body; без поля тип угадывается по содержимому
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_type: Option<BodyKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub weight: Option<u32>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign: Option<SigningConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<GraphqlConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<StreamConfig>,
+    // Unix-сокет, через который отправляются запросы к url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_path: Option<String>,
+    // Пауза воркера после запроса к url, в миллисекундах
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub think_time_ms: Option<u64>,
+    // Группа в отчете, например тег операции OpenAPI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+// Профиль стенда: значения поверх основных полей файла, выбирается через --profile
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProfileConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    // Добавляются к common_headers, при совпадении имени заменяют их
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<String>,
+    // Число пользователей без rps; --users важнее
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub users: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_requests: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rps: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct MultiTestConfig {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    // Адрес, к которому добавляются относительные url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
     pub urls: Vec<UrlConfig>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub distribution: Option<String>,
-    #[serde(default)]
+    // Число пользователей без rps; --users важнее
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub users: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub total_requests: Option<usize>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rps: Option<usize>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub duration_seconds: Option<u64>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub common_headers: Option<HashMap<String, String>>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub common_body: Option<String>,
+    // Подставлять {{userId}}, {{timestamp}}, {{uuid}} в body каждого запроса
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamic_body: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<HashMap<String, ProfileConfig>>,
 }
 
 impl Default for MultiTestConfig {
     fn default() -> Self {
         Self {
             name: None,
+            base_url: None,
             urls: Vec::new(),
             distribution: Some("round-robin".to_string()),
+            users: None,
             total_requests: None,
             rps: Some(10),
             duration_seconds: Some(10),
             common_headers: None,
             common_body: None,
+            dynamic_body: None,
+            profiles: None,
+        }
+    }
+}
+
+impl MultiTestConfig {
+    // Загружает конфигурацию по расширению файла (JSON, YAML, TOML): собирает цепочку extends,
+    // подставляет ${VAR} и ${secret:файл} во все строки, применяет профиль и base_url.
+    // Файл с другим расширением читается как список URL по одному на строку.
+    pub fn load(path: &str, env: &ConfigEnv, profile: Option<&str>) -> Result<Self, String> {
+        let extension = path.rsplit('.').next().unwrap_or("").to_lowercase();
+        let mut config: Self = if matches!(extension.as_str(), "json" | "yaml" | "yml" | "toml") {
+            let mut document = load_document(Path::new(path), &mut Vec::new())?;
+            select_profile(&mut document, profile).map_err(|e| format!("{}: {}", path, e))?;
+            let document = env.interpolate_value(document).map_err(|e| format!("{}: {}", path, e))?;
+            serde_json::from_value(document).map_err(|e| format!("Ошибка разбора {}: {}", path, e))?
+        } else if profile.is_some() {
+            return Err(format!("{}: профили поддерживаются только в файлах JSON, YAML и TOML", path));
+        } else {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?;
+            let base_dir = Path::new(path).canonicalize()
+                .map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?;
+            let content = anchor_text(&content, base_dir.parent().unwrap_or(Path::new("")));
+            Self::from_url_list(&env.interpolate(&content).map_err(|e| format!("{}: {}", path, e))?)
+        };
+
+        let selected = profile.and_then(|name| config.profiles.as_ref()?.get(name).cloned());
+        if let Some(selected) = selected {
+            config.apply_profile(selected);
+        }
+        config.resolve_urls()?;
+
+        if config.urls.is_empty() {
+            return Err("No URLs found in config file".to_string());
+        }
+        Ok(config)
+    }
+
+    fn apply_profile(&mut self, profile: ProfileConfig) {
+        self.base_url = profile.base_url.or(self.base_url.take());
+        self.distribution = profile.distribution.or(self.distribution.take());
+        self.users = profile.users.or(self.users);
+        self.total_requests = profile.total_requests.or(self.total_requests);
+        self.rps = profile.rps.or(self.rps);
+        self.duration_seconds = profile.duration_seconds.or(self.duration_seconds);
+        if let Some(headers) = profile.headers {
+            self.common_headers.get_or_insert_with(HashMap::new).extend(headers);
+        }
+    }
+
+    // Относительные url дописываются к base_url
+    fn resolve_urls(&mut self) -> Result<(), String> {
+        for url_config in &mut self.urls {
+            if url_config.url.contains("://") {
+                continue;
+            }
+            let base_url = self.base_url.as_deref()
+                .ok_or_else(|| format!("URL {} относительный, а base_url не задан", url_config.url))?;
+            url_config.url = format!("{}/{}", base_url.trim_end_matches('/'), url_config.url.trim_start_matches('/'));
+        }
+        Ok(())
+    }
+
+    // Сохраняет конфигурацию в формате по расширению файла (JSON, YAML, TOML)
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let extension = path.rsplit('.').next().unwrap_or("").to_lowercase();
+        let content = match extension.as_str() {
+            "json" => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            "yaml" | "yml" => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+            "toml" => toml::to_string(self).map_err(|e| e.to_string()),
+            _ => Err("поддерживаются расширения .json, .yaml, .yml и .toml".to_string()),
         }
+        .map_err(|e| format!("Не удалось сохранить {}: {}", path, e))?;
+
+        std::fs::write(path, content)
+            .map_err(|e| format!("Не удалось записать {}: {}", path, e))
     }
-}
\ No newline at end of file
+
+    // Простой формат: URL по одному на строку, # - комментарий
+    fn from_url_list(content: &str) -> Self {
+        Self::from_urls(
+            content
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| line.to_string()),
+        )
+    }
+
+    // Конфигурация только из адресов; режим нагрузки задается из CLI
+    pub fn from_urls(urls: impl IntoIterator<Item = String>) -> Self {
+        let urls = urls
+            .into_iter()
+            .map(|url| UrlConfig {
+                url,
+                method: None,
+                body: None,
+                body_type: None,
+                headers: None,
+                weight: None,
+                timeout: None,
+                tls: None,
+                auth: None,
+                sign: None,
+                graphql: None,
+                stream: None,
+                socket_path: None,
+                think_time_ms: None,
+                group: None,
+            })
+            .collect();
+
+        Self {
+            urls,
+            distribution: None,
+            rps: None,
+            duration_seconds: None,
+            ..Default::default()
+        }
+    }
+}
+
+// Читает файл и базовые файлы из extends; поля наследника заменяют поля базового,
+// объекты (common_headers, profiles) объединяются по ключам, списки заменяются целиком
+fn load_document(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Value, String> {
+    let display = path.display();
+    let canonical = path.canonicalize()
+        .map_err(|e| format!("Не удалось прочитать {}: {}", display, e))?;
+    if chain.contains(&canonical) {
+        return Err(format!("Цикл в extends: {}", display));
+    }
+    chain.push(canonical.clone());
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Не удалось прочитать {}: {}", display, e))?;
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let document: Value = match extension.as_str() {
+        "json" => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+        "toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+        _ => Err("extends поддерживается для .json, .yaml, .yml и .toml".to_string()),
+    }
+    .map_err(|e| format!("Ошибка разбора {}: {}", display, e))?;
+    // ${secret:путь} - относительно файла, в котором записан, как и extends
+    let mut document = anchor_secret_paths(document, canonical.parent().unwrap_or(Path::new("")));
+
+    match document.as_object_mut().and_then(|fields| fields.remove("extends")) {
+        // Путь к базовому файлу - относительно файла с extends
+        Some(Value::String(parent)) => {
+            let parent = path.parent().unwrap_or(Path::new("")).join(parent);
+            let mut base = load_document(&parent, chain)?;
+            merge(&mut base, document);
+            Ok(base)
+        }
+        Some(_) => Err(format!("{}: extends должен быть путем к файлу", display)),
+        None => Ok(document),
+    }
+}
+
+// Оставляет в документе только выбранный профиль: переменные и секреты
+// остальных профилей не подставляются и могут быть не заданы
+fn select_profile(document: &mut Value, profile: Option<&str>) -> Result<(), String> {
+    let profiles = document.get_mut("profiles").and_then(Value::as_object_mut);
+    match (profiles, profile) {
+        (Some(profiles), Some(name)) if profiles.contains_key(name) => {
+            profiles.retain(|key, _| key == name);
+            Ok(())
+        }
+        (Some(profiles), Some(name)) => {
+            let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            Err(format!("профиль {} не найден; доступны: {}", name, names.join(", ")))
+        }
+        (None, Some(name)) => Err(format!("профиль {} не найден: в файле нет profiles", name)),
+        (Some(profiles), None) => {
+            profiles.clear();
+            Ok(())
+        }
+        (None, None) => Ok(()),
+    }
+}
+
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Каталог с файлами конфигурации во временном каталоге; удаляется после теста
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("load_test_{}_{}", std::process::id(), name));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, name: &str, content: &str) -> PathBuf {
+            let path = self.0.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn load(path: &Path, profile: Option<&str>) -> Result<MultiTestConfig, String> {
+        MultiTestConfig::load(path.to_str().unwrap(), &ConfigEnv::default(), profile)
+    }
+
+    #[test]
+    fn extends_cycle_is_detected() {
+        let dir = TempDir::new("extends_cycle");
+        let first = dir.write("first.yaml", "extends: second.yaml\nurls: [{ url: http://localhost/a }]\n");
+        dir.write("second.yaml", "extends: first.yaml\n");
+        let error = load_document(&first, &mut Vec::new()).unwrap_err();
+        assert!(error.starts_with("Цикл в extends"), "{}", error);
+
+        let own = dir.write("own.json", r#"{"extends": "./own.json", "urls": []}"#);
+        let error = load_document(&own, &mut Vec::new()).unwrap_err();
+        assert!(error.starts_with("Цикл в extends"), "{}", error);
+    }
+
+    #[test]
+    fn extends_path_is_relative_to_the_extending_file() {
+        let dir = TempDir::new("extends_relative");
+        // Базовый файл наследует еще один - относительно своего каталога, а не каталога наследника
+        dir.write("base/root.toml", "distribution = \"random\"\nurls = []\n");
+        dir.write("base/shared.yaml", "\
+extends: root.toml
+name: shop
+base_url: http://localhost:8080
+rps: 10
+urls:
+  - url: /users
+");
+        let child = dir.write("envs/staging/test.json", r#"{"extends": "../../base/shared.yaml", "rps": 50}"#);
+
+        let config = load(&child, None).unwrap();
+        assert_eq!(config.name.as_deref(), Some("shop"));
+        assert_eq!(config.distribution.as_deref(), Some("random"));
+        assert_eq!(config.rps, Some(50));
+        assert_eq!(config.urls.len(), 1);
+        assert_eq!(config.urls[0].url, "http://localhost:8080/users");
+
+        let missing = dir.write("envs/missing.yaml", "extends: shared.yaml\n");
+        let error = load(&missing, None).unwrap_err();
+        assert!(error.starts_with("Не удалось прочитать") && error.contains("shared.yaml"), "{}", error);
+    }
+
+    #[test]
+    fn secret_paths_are_relative_to_the_file_that_contains_them() {
+        let dir = TempDir::new("secret_relative");
+        dir.write("base/secrets/token.txt", "base-token-1\n");
+        dir.write("base/shared.yaml", "\
+common_headers:
+  Authorization: Bearer ${secret:secrets/token.txt}
+urls:
+  - url: http://localhost/users
+");
+        dir.write("envs/secrets/key.txt", "child-key-22\n");
+        let child = dir.write("envs/test.yaml", "\
+extends: ../base/shared.yaml
+common_headers:
+  X-Api-Key: ${secret:./secrets/key.txt}
+");
+        let list = dir.write("envs/urls.txt", "http://localhost/a?key=${secret:secrets/key.txt}\n");
+
+        let headers = load(&child, None).unwrap().common_headers.unwrap();
+        assert_eq!(headers["Authorization"], "Bearer base-token-1");
+        assert_eq!(headers["X-Api-Key"], "child-key-22");
+        assert_eq!(load(&list, None).unwrap().urls[0].url, "http://localhost/a?key=child-key-22");
+    }
+
+    #[test]
+    fn merge_joins_objects_by_key_and_replaces_lists() {
+        let mut base = json!({
+            "name": "shop",
+            "rps": 10,
+            "common_headers": { "X-Source": "load_test", "Accept": "application/json" },
+            "profiles": { "staging": { "rps": 5, "headers": { "X-Env": "staging" } } },
+            "urls": [{ "url": "/users" }, { "url": "/orders" }],
+        });
+        merge(&mut base, json!({
+            "rps": 50,
+            "common_headers": { "Accept": "text/plain", "X-Trace": "1" },
+            "profiles": { "staging": { "headers": { "X-Debug": "1" } }, "prod": { "rps": 100 } },
+            "urls": [{ "url": "/health" }],
+        }));
+
+        assert_eq!(base, json!({
+            "name": "shop",
+            "rps": 50,
+            "common_headers": { "X-Source": "load_test", "Accept": "text/plain", "X-Trace": "1" },
+            "profiles": {
+                "staging": { "rps": 5, "headers": { "X-Env": "staging", "X-Debug": "1" } },
+                "prod": { "rps": 100 },
+            },
+            "urls": [{ "url": "/health" }],
+        }));
+    }
+
+    #[test]
+    fn unknown_profile_is_an_error() {
+        let mut document = json!({ "profiles": { "staging": {}, "prod": {} }, "urls": [] });
+        let error = select_profile(&mut document, Some("dev")).unwrap_err();
+        assert!(error.starts_with("профиль dev не найден; доступны: "), "{}", error);
+        assert!(error.contains("staging") && error.contains("prod"), "{}", error);
+
+        let mut document = json!({ "urls": [] });
+        assert_eq!(
+            select_profile(&mut document, Some("dev")).unwrap_err(),
+            "профиль dev не найден: в файле нет profiles",
+        );
+
+        let dir = TempDir::new("unknown_profile");
+        let list = dir.write("urls.txt", "http://localhost/a\n");
+        let error = load(&list, Some("dev")).unwrap_err();
+        assert!(error.ends_with("профили поддерживаются только в файлах JSON, YAML и TOML"), "{}", error);
+    }
+
+    #[test]
+    fn only_selected_profile_is_interpolated() {
+        let dir = TempDir::new("selected_profile");
+        let path = dir.write("shop.yaml", "\
+base_url: http://localhost:8080
+urls:
+  - url: /users
+profiles:
+  staging:
+    base_url: http://staging.local
+    rps: 5
+    headers: { X-Env: staging }
+  prod:
+    base_url: ${LOAD_TEST_UNSET_PROD_URL}
+");
+        let config = load(&path, Some("staging")).unwrap();
+        assert_eq!(config.urls[0].url, "http://staging.local/users");
+        assert_eq!(config.rps, Some(5));
+        assert_eq!(config.common_headers.unwrap()["X-Env"], "staging");
+        assert_eq!(config.profiles.unwrap().keys().collect::<Vec<_>>(), vec!["staging"]);
+
+        let error = load(&path, Some("prod")).unwrap_err();
+        assert!(error.contains("LOAD_TEST_UNSET_PROD_URL не задана"), "{}", error);
+    }
+}
@@ -0,0 +1,118 @@
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SIGNATURE_HEADER: &str = "X-Signature";
+pub const DEFAULT_TIMESTAMP_HEADER: &str = "X-Timestamp";
+// Подставляемые значения: {method}, {path}, {host}, {timestamp}, {body_sha256}, {header:Имя}
+pub const DEFAULT_SIGNATURE_TEMPLATE: &str = "{method}\\n{path}\\n{timestamp}\\n{body_sha256}";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+// Подпись запросов: задается в командной строке или в поле `sign` у URL в файле multi
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SigningConfig {
+    // HMAC-SHA256 от строки, собранной по шаблону
+    Hmac {
+        secret: String,
+        #[serde(default = "default_signature_header")]
+        header: String,
+        #[serde(default = "default_signature_template")]
+        template: String,
+        #[serde(default = "default_timestamp_header")]
+        timestamp_header: String,
+        #[serde(default)]
+        encoding: SignatureEncoding,
+    },
+    // AWS Signature Version 4; ключи по умолчанию берутся из AWS_ACCESS_KEY_ID,
+    // AWS_SECRET_ACCESS_KEY и AWS_SESSION_TOKEN
+    #[serde(rename = "aws_sigv4")]
+    AwsSigV4 {
+        region: String,
+        service: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        access_key_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        secret_access_key: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_token: Option<String>,
+    },
+}
+
+fn default_signature_header() -> String {
+    DEFAULT_SIGNATURE_HEADER.to_string()
+}
+
+fn default_signature_template() -> String {
+    DEFAULT_SIGNATURE_TEMPLATE.to_string()
+}
+
+fn default_timestamp_header() -> String {
+    DEFAULT_TIMESTAMP_HEADER.to_string()
+}
+
+// Подпись из командной строки; в режиме multi действует для URL без своего поля `sign`
+#[derive(Args, Debug, Clone, Default)]
+pub struct SigningOptions {
+    /// Подписывать запросы HMAC-SHA256 с этим секретом
+    #[arg(long, conflicts_with = "aws_sigv4")]
+    pub hmac_secret: Option<String>,
+
+    /// Заголовок с подписью HMAC [по умолчанию: X-Signature]
+    #[arg(long, requires = "hmac_secret")]
+    pub hmac_header: Option<String>,
+
+    /// Шаблон подписываемой строки: {method}, {path}, {host}, {timestamp}, {body_sha256},
+    /// {header:Имя}; \n - перевод строки [по умолчанию: {method}\n{path}\n{timestamp}\n{body_sha256}]
+    #[arg(long, requires = "hmac_secret")]
+    pub hmac_template: Option<String>,
+
+    /// Заголовок с меткой времени, если она есть в шаблоне [по умолчанию: X-Timestamp]
+    #[arg(long, requires = "hmac_secret")]
+    pub hmac_timestamp_header: Option<String>,
+
+    /// Кодировка подписи HMAC
+    #[arg(long, value_enum, requires = "hmac_secret")]
+    pub hmac_encoding: Option<SignatureEncoding>,
+
+    /// Подписывать запросы AWS SigV4: регион и сервис в формате region:service
+    #[arg(long, value_name = "REGION:SERVICE")]
+    pub aws_sigv4: Option<String>,
+}
+
+impl SigningOptions {
+    pub fn config(&self) -> Result<Option<SigningConfig>, String> {
+        if let Some(secret) = &self.hmac_secret {
+            return Ok(Some(SigningConfig::Hmac {
+                secret: secret.clone(),
+                header: self.hmac_header.clone().unwrap_or_else(default_signature_header),
+                template: self.hmac_template.clone().unwrap_or_else(default_signature_template),
+                timestamp_header: self
+                    .hmac_timestamp_header
+                    .clone()
+                    .unwrap_or_else(default_timestamp_header),
+                encoding: self.hmac_encoding.unwrap_or_default(),
+            }));
+        }
+
+        let Some(scope) = &self.aws_sigv4 else {
+            return Ok(None);
+        };
+        let Some((region, service)) = scope.split_once(':') else {
+            return Err(format!("Ожидается --aws-sigv4 region:service: {}", scope));
+        };
+        Ok(Some(SigningConfig::AwsSigV4 {
+            region: region.to_string(),
+            service: service.to_string(),
+            access_key_id: None,
+            secret_access_key: None,
+            session_token: None,
+        }))
+    }
+}
@@ -0,0 +1,39 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+// Чтение потокового ответа (SSE или chunked): задается в командной строке
+// или в поле `stream` у URL в файле multi
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct StreamConfig {
+    // Сколько секунд держать поток открытым; закрытие сервером раньше - обрыв
+    #[serde(default)]
+    pub duration: Option<u64>,
+    // Событие или chunk с этим текстом завершает поток; закрытие без него - обрыв
+    #[serde(default)]
+    pub end_marker: Option<String>,
+}
+
+// Потоковый режим из командной строки; в режиме multi действует для URL без своего поля `stream`
+#[derive(Args, Debug, Clone, Default)]
+pub struct StreamOptions {
+    /// Читать ответ как поток: время до первого события, паузы между событиями, обрывы
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Сколько секунд держать поток открытым (для бесконечных потоков SSE)
+    #[arg(long, requires = "stream")]
+    pub stream_duration: Option<u64>,
+
+    /// Текст, которым заканчивается поток, например [DONE]
+    #[arg(long, requires = "stream")]
+    pub stream_end: Option<String>,
+}
+
+impl StreamOptions {
+    pub fn config(&self) -> Option<StreamConfig> {
+        self.stream.then(|| StreamConfig {
+            duration: self.stream_duration,
+            end_marker: self.stream_end.clone(),
+        })
+    }
+}
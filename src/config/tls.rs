@@ -0,0 +1,103 @@
+use std::fmt;
+
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize, Serialize)]
+pub enum TlsVersion {
+    #[value(name = "1.0")]
+    #[serde(rename = "1.0")]
+    Tls1_0,
+    #[value(name = "1.1")]
+    #[serde(rename = "1.1")]
+    Tls1_1,
+    #[value(name = "1.2")]
+    #[serde(rename = "1.2")]
+    Tls1_2,
+    #[value(name = "1.3")]
+    #[serde(rename = "1.3")]
+    Tls1_3,
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TlsVersion::Tls1_0 => "1.0",
+            TlsVersion::Tls1_1 => "1.1",
+            TlsVersion::Tls1_2 => "1.2",
+            TlsVersion::Tls1_3 => "1.3",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl From<TlsVersion> for reqwest::tls::Version {
+    fn from(version: TlsVersion) -> Self {
+        match version {
+            TlsVersion::Tls1_0 => reqwest::tls::Version::TLS_1_0,
+            TlsVersion::Tls1_1 => reqwest::tls::Version::TLS_1_1,
+            TlsVersion::Tls1_2 => reqwest::tls::Version::TLS_1_2,
+            TlsVersion::Tls1_3 => reqwest::tls::Version::TLS_1_3,
+        }
+    }
+}
+
+// Настройки TLS: задаются в командной строке или в поле `tls` у URL в файле multi
+#[derive(Args, Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TlsOptions {
+    /// Сертификаты доверенного CA в формате PEM
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cacert: Option<String>,
+
+    /// Клиентский сертификат для mTLS: PEM или PKCS#12 (.p12, .pfx)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert: Option<String>,
+
+    /// Приватный ключ PKCS#8 в формате PEM для сертификата --cert
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+
+    /// Пароль для сертификата PKCS#12
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert_password: Option<String>,
+
+    /// Не проверять сертификат и имя сервера
+    #[arg(short = 'k', long)]
+    pub insecure: bool,
+
+    /// Минимальная версия TLS
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_min_version: Option<TlsVersion>,
+
+    /// Имя сервера для SNI, проверки сертификата и Host; соединение идет на адрес из URL
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_server_name: Option<String>,
+}
+
+impl TlsOptions {
+    // Настройки конкретного URL поверх общих: заданные поля override имеют приоритет
+    pub fn merged_with(&self, overrides: &TlsOptions) -> TlsOptions {
+        TlsOptions {
+            cacert: overrides.cacert.clone().or_else(|| self.cacert.clone()),
+            cert: overrides.cert.clone().or_else(|| self.cert.clone()),
+            key: overrides.key.clone().or_else(|| self.key.clone()),
+            cert_password: overrides
+                .cert_password
+                .clone()
+                .or_else(|| self.cert_password.clone()),
+            insecure: self.insecure || overrides.insecure,
+            tls_min_version: overrides.tls_min_version.or(self.tls_min_version),
+            tls_server_name: overrides
+                .tls_server_name
+                .clone()
+                .or_else(|| self.tls_server_name.clone()),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use base64::{engine::general_purpose, Engine as _};
+use hyper::client::connect::dns::Name;
+use load_test::{ClientOptions, NO_PROXY_ALL};
+use reqwest::dns::Resolve;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpSocket, TcpStream};
+use tokio_socks::tcp::Socks5Stream;
+use url::Url;
+
+use crate::dns::SpreadResolver;
+
+// Предел заголовков ответа прокси на CONNECT
+const MAX_PROXY_RESPONSE: usize = 8 * 1024;
+
+// TCP-соединения для клиентов в обход reqwest (ws) с теми же --resolve, --dns-spread,
+// --proxy, --no-proxy и --local-address. Прокси из переменных окружения здесь не используются.
+pub(crate) struct TcpConnector {
+    resolver: Option<SpreadResolver>,
+    proxy: Option<Url>,
+    no_proxy: Vec<String>,
+    local_addresses: Vec<IpAddr>,
+    nodelay: bool,
+}
+
+impl TcpConnector {
+    pub(crate) fn new(options: &ClientOptions) -> Result<Self, String> {
+        let no_proxy = options.no_proxy.as_deref();
+        let proxy = match (&options.proxy, no_proxy) {
+            (_, Some(NO_PROXY_ALL)) => None,
+            (None, Some(_)) => return Err("Список хостов --no-proxy задается вместе с --proxy".to_string()),
+            (None, None) => None,
+            (Some(proxy), _) => {
+                let url = Url::parse(proxy).map_err(|e| format!("Некорректный прокси {}: {}", proxy, e))?;
+                if !matches!(url.scheme(), "http" | "socks5" | "socks5h") {
+                    return Err(format!("Прокси {} не поддерживается: нужен http://, socks5:// или socks5h://", proxy));
+                }
+                Some(url)
+            }
+        };
+
+        Ok(Self {
+            resolver: SpreadResolver::from_options(options)?,
+            proxy,
+            no_proxy: no_proxy
+                .map(|hosts| hosts.split(',').map(|host| host.trim().trim_start_matches('.').to_lowercase()).collect())
+                .unwrap_or_default(),
+            local_addresses: options.local_address.clone(),
+            nodelay: options.tcp_nodelay,
+        })
+    }
+
+    // Соединение с host:port; worker выбирает локальный адрес, как ClientSet::for_worker
+    pub(crate) async fn connect(&self, host: &str, port: u16, worker: usize) -> io::Result<TcpStream> {
+        let local = (!self.local_addresses.is_empty())
+            .then(|| self.local_addresses[worker % self.local_addresses.len()]);
+        let stream = match self.proxy.as_ref().filter(|_| !self.bypasses_proxy(host)) {
+            Some(proxy) => self.connect_proxy(proxy, host, port, local).await?,
+            None => connect_any(&self.resolve(host, port).await?, local).await?,
+        };
+        stream.set_nodelay(self.nodelay)?;
+        Ok(stream)
+    }
+
+    async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![SocketAddr::new(ip, port)]);
+        }
+        let Some(resolver) = &self.resolver else {
+            return Ok(tokio::net::lookup_host((host, port)).await?.collect());
+        };
+        let name = Name::from_str(host).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let addrs = resolver.resolve(name).await.map_err(io::Error::other)?;
+        // Обычное разрешение имени возвращает порт 0, --resolve - порт из подмены
+        Ok(addrs
+            .map(|addr| if addr.port() == 0 { SocketAddr::new(addr.ip(), port) } else { addr })
+            .collect())
+    }
+
+    fn bypasses_proxy(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.no_proxy.iter().any(|pattern| {
+            !pattern.is_empty() && (host == *pattern || host.ends_with(&format!(".{}", pattern)))
+        })
+    }
+
+    async fn connect_proxy(&self, proxy: &Url, host: &str, port: u16, local: Option<IpAddr>) -> io::Result<TcpStream> {
+        let proxy_host = proxy.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+        let proxy_port = proxy.port_or_known_default().unwrap_or(1080);
+        let stream = connect_any(&self.resolve(proxy_host, proxy_port).await?, local).await?;
+        let username = percent_decode(proxy.username());
+        let password = percent_decode(proxy.password().unwrap_or_default());
+
+        match proxy.scheme() {
+            "http" => http_connect(stream, host, port, &username, &password).await,
+            scheme => {
+                // socks5h разрешает имя на прокси, socks5 - здесь
+                let target = match scheme {
+                    "socks5h" => tokio_socks::TargetAddr::Domain(host.into(), port),
+                    _ => tokio_socks::TargetAddr::Ip(*self.resolve(host, port).await?.first().ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::NotFound, format!("Нет адресов для {}", host))
+                    })?),
+                };
+                let stream = if username.is_empty() {
+                    Socks5Stream::connect_with_socket(stream, target).await
+                } else {
+                    Socks5Stream::connect_with_password_and_socket(stream, target, &username, &password).await
+                };
+                stream.map(Socks5Stream::into_inner).map_err(|e| io::Error::other(format!("Ошибка SOCKS5-прокси: {}", e)))
+            }
+        }
+    }
+}
+
+// Первое удавшееся соединение из адресов; с локальным адресом - только адреса того же семейства
+async fn connect_any(addrs: &[SocketAddr], local: Option<IpAddr>) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "Нет адресов для подключения");
+    for addr in addrs.iter().filter(|addr| local.is_none_or(|local| local.is_ipv4() == addr.is_ipv4())) {
+        let socket = if addr.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+        if let Some(local) = local {
+            socket.bind(SocketAddr::new(local, 0))?;
+        }
+        match socket.connect(*addr).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+// Туннель через HTTP-прокси методом CONNECT
+async fn http_connect(mut stream: TcpStream, host: &str, port: u16, username: &str, password: &str) -> io::Result<TcpStream> {
+    let authority = if host.contains(':') { format!("[{}]:{}", host, port) } else { format!("{}:{}", host, port) };
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if !username.is_empty() {
+        let credentials = general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !response.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || response.len() + read > MAX_PROXY_RESPONSE {
+            return Err(io::Error::other("Прокси закрыл соединение без ответа на CONNECT"));
+        }
+        response.extend_from_slice(&buffer[..read]);
+    }
+
+    let status_line = String::from_utf8_lossy(&response);
+    let status_line = status_line.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(stream),
+        _ => Err(io::Error::other(format!("Прокси отклонил CONNECT {}: {}", authority, status_line))),
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    percent_encoding::percent_decode_str(value).decode_utf8_lossy().into_owned()
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use hyper::client::connect::dns::Name;
+use load_test::ClientOptions;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use url::Url;
+
+// Адреса одного хоста, которые новые соединения получают по очереди
+struct AddressRing {
+    addrs: Vec<SocketAddr>,
+    next: AtomicUsize,
+}
+
+impl AddressRing {
+    fn new(addrs: Vec<SocketAddr>) -> Self {
+        Self {
+            addrs,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    // Все адреса, начиная со следующего по очереди: остальные остаются запасными
+    fn rotated(&self) -> Addrs {
+        if self.addrs.is_empty() {
+            return Box::new(std::iter::empty());
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.addrs.len();
+        let mut addrs = self.addrs.clone();
+        addrs.rotate_left(start);
+        Box::new(addrs.into_iter())
+    }
+}
+
+// Резолвер для --resolve и --dns-spread. Вызывается на каждое новое соединение,
+// поэтому нагрузка распределяется по адресам на уровне соединений, а не запросов.
+pub(crate) struct SpreadResolver {
+    overrides: HashMap<String, Arc<AddressRing>>,
+    spread: bool,
+    // Результаты первого разрешения имен для --dns-spread
+    resolved: Arc<Mutex<HashMap<String, Arc<AddressRing>>>>,
+}
+
+impl SpreadResolver {
+    // None, если стандартного резолвера достаточно
+    pub(crate) fn from_options(options: &ClientOptions) -> Result<Option<Self>, String> {
+        if options.resolve.is_empty() && !options.dns_spread {
+            return Ok(None);
+        }
+
+        // Повторный --resolve для того же хоста добавляет адреса. Резолвер получает только имя,
+        // поэтому разные порты одного хоста развести нельзя - это ошибка, а не слияние
+        let mut addrs: HashMap<String, Vec<SocketAddr>> = HashMap::new();
+        for entry in &options.resolve {
+            let host_addrs = addrs.entry(entry.host.clone()).or_default();
+            if let Some(other) = host_addrs.first().filter(|addr| addr.port() != entry.port) {
+                return Err(format!(
+                    "--resolve для {} указан с разными портами: {} и {}",
+                    entry.host, other.port(), entry.port
+                ));
+            }
+            host_addrs.extend(entry.addrs.iter().map(|ip| SocketAddr::new(*ip, entry.port)));
+        }
+
+        Ok(Some(Self {
+            overrides: addrs
+                .into_iter()
+                .map(|(host, addrs)| (host, Arc::new(AddressRing::new(addrs))))
+                .collect(),
+            spread: options.dns_spread,
+            resolved: Arc::new(Mutex::new(HashMap::new())),
+        }))
+    }
+}
+
+// --resolve задан для host:port, как в curl. Подмена в резолвере действует на все порты хоста,
+// поэтому URL того же хоста на другом порту - ошибка, а не молчаливая подмена адреса
+pub(crate) fn check_port(options: &ClientOptions, url: &Url) -> Result<(), String> {
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else { return Ok(()) };
+    let host = host.to_lowercase();
+    match options.resolve.iter().find(|entry| entry.host == host && entry.port != port) {
+        Some(entry) => Err(format!(
+            "--resolve {}:{} не относится к {}: порт URL {}",
+            entry.host, entry.port, url, port
+        )),
+        None => Ok(()),
+    }
+}
+
+impl Resolve for SpreadResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_lowercase();
+        if let Some(ring) = self.overrides.get(&host) {
+            let addrs = ring.rotated();
+            return Box::pin(async move { Ok(addrs) });
+        }
+
+        let spread = self.spread;
+        let resolved = self.resolved.clone();
+        Box::pin(async move {
+            if spread && let Some(ring) = resolved.lock().unwrap().get(&host).cloned() {
+                return Ok(ring.rotated());
+            }
+
+            // Порт подставит коннектор из URL
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)?
+                .collect();
+            if !spread {
+                return Ok(Box::new(addrs.into_iter()) as Addrs);
+            }
+
+            // Параллельные соединения могли разрешить имя одновременно: используем первый результат
+            let ring = resolved
+                .lock()
+                .unwrap()
+                .entry(host)
+                .or_insert_with(|| Arc::new(AddressRing::new(addrs)))
+                .clone();
+            Ok(ring.rotated())
+        })
+    }
+}
+
+// Краткое описание для заголовка теста
+pub(crate) fn describe(options: &ClientOptions) -> Vec<String> {
+    let mut parts: Vec<String> = options
+        .resolve
+        .iter()
+        .map(|entry| {
+            let addrs: Vec<String> = entry.addrs.iter().map(|ip| ip.to_string()).collect();
+            format!("{}:{} → {}", entry.host, entry.port, addrs.join(", "))
+        })
+        .collect();
+    if options.dns_spread {
+        parts.push("DNS: все адреса по очереди".to_string());
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        client: ClientOptions,
+    }
+
+    fn options(args: &[&str]) -> ClientOptions {
+        Cli::try_parse_from(std::iter::once("load_test").chain(args.iter().copied())).unwrap().client
+    }
+
+    fn check(options: &ClientOptions, url: &str) -> Result<(), String> {
+        check_port(options, &Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn port_must_match_resolve_entry() {
+        let options = options(&["--resolve", "api.example.com:443:10.0.0.1"]);
+        assert!(check(&options, "https://api.example.com/v1").is_ok());
+        assert!(check(&options, "https://API.example.com:443/").is_ok());
+        assert!(check(&options, "https://other.example.com:8443/").is_ok());
+
+        let error = check(&options, "http://api.example.com/").unwrap_err();
+        assert!(error.contains("--resolve api.example.com:443"), "{}", error);
+        assert!(error.contains("порт URL 80"), "{}", error);
+        assert!(check(&options, "https://api.example.com:8443/").is_err());
+    }
+
+    #[test]
+    fn repeated_resolve_merges_addresses_of_one_port() {
+        let resolver = SpreadResolver::from_options(&options(&[
+            "--resolve", "api.example.com:443:10.0.0.1,[::1]",
+            "--resolve", "API.example.com:443:10.0.0.2",
+        ])).unwrap().unwrap();
+        let ring = &resolver.overrides["api.example.com"];
+        let addrs: Vec<String> = ring.addrs.iter().map(SocketAddr::to_string).collect();
+        assert_eq!(addrs, ["10.0.0.1:443", "[::1]:443", "10.0.0.2:443"]);
+
+        let error = SpreadResolver::from_options(&options(&[
+            "--resolve", "api.example.com:443:10.0.0.1",
+            "--resolve", "api.example.com:8443:10.0.0.2",
+        ])).err().unwrap();
+        assert!(error.contains("с разными портами: 443 и 8443"), "{}", error);
+
+        assert!(SpreadResolver::from_options(&options(&[])).unwrap().is_none());
+    }
+
+    #[test]
+    fn new_connections_get_addresses_in_turn() {
+        let ring = AddressRing::new(vec!["10.0.0.1:80".parse().unwrap(), "10.0.0.2:80".parse().unwrap()]);
+        let first: Vec<SocketAddr> = ring.rotated().collect();
+        let second: Vec<SocketAddr> = ring.rotated().collect();
+        let third: Vec<SocketAddr> = ring.rotated().collect();
+        assert_eq!(first[0].to_string(), "10.0.0.1:80");
+        assert_eq!(second[0].to_string(), "10.0.0.2:80");
+        assert_eq!(first, third);
+        // Остальные адреса остаются запасными
+        assert_eq!(second.len(), 2);
+        assert_eq!(AddressRing::new(Vec::new()).rotated().count(), 0);
+    }
+}
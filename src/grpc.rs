@@ -0,0 +1,611 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use clap::Args;
+use hyper_util::rt::TokioIo;
+use load_test::{AbortCondition, ClientOptions, TlsVersion};
+use prost::Message as _;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::codegen::http::Uri;
+use tonic::codegen::Service;
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic::{Code, Status};
+
+use crate::auth::{AuthProviders, Authorization};
+use crate::client::{self, IdentityFiles};
+use crate::connect::TcpConnector;
+use crate::guardrails::Guardrails;
+use crate::shutdown::{StopHandle, StopSignal};
+use crate::stats::{classify_transport, spawn_aggregator, ErrorClass, RequestResult};
+use crate::{join_workers, load_jobs, print_stop_notice, JobSource, FAILURE_SAMPLES};
+
+// Коды gRPC от OK (0) до UNAUTHENTICATED (16)
+const GRPC_CODES: usize = 17;
+
+#[derive(Args)]
+pub(crate) struct GrpcConfig {
+    /// Адрес сервера (http:// - без шифрования, https:// - TLS)
+    #[arg(short = 'U', long, default_value = "http://localhost:50051")]
+    url: String,
+
+    /// .proto-файл с описанием сервиса (компилируется через protoc)
+    #[arg(long, conflicts_with = "descriptor_set", required_unless_present = "descriptor_set")]
+    proto: Option<PathBuf>,
+
+    /// Каталоги импорта для --proto (можно указать несколько; по умолчанию каталог файла)
+    #[arg(short = 'I', long = "import-path")]
+    import_paths: Vec<PathBuf>,
+
+    /// Готовый набор дескрипторов (protoc --include_imports --descriptor_set_out)
+    #[arg(long)]
+    descriptor_set: Option<PathBuf>,
+
+    /// Метод в формате package.Service/Method
+    #[arg(short, long)]
+    method: String,
+
+    /// Запрос в JSON: {{userId}}, {{timestamp}}, {{uuid}}
+    #[arg(short = 'd', long, default_value = "{}")]
+    data: String,
+
+    /// Метаданные в формате "Key: Value" (можно указать несколько)
+    #[arg(short = 'H', long)]
+    headers: Vec<String>,
+
+    /// Количество HTTP/2-соединений, между которыми распределяются воркеры
+    #[arg(long, default_value_t = 1)]
+    connections: usize,
+
+    /// Количество одновременных вызовов
+    #[arg(short, long, default_value_t = 10)]
+    concurrency: usize,
+
+    /// Всего вызовов (по умолчанию равно --concurrency, с --duration - без ограничения)
+    #[arg(short = 'n', long)]
+    total_requests: Option<usize>,
+
+    /// Вызовов в секунду (без ограничения, если не задано)
+    #[arg(short, long)]
+    rps: Option<usize>,
+
+    /// Длительность теста в секундах (с --rps по умолчанию 10)
+    #[arg(long)]
+    duration: Option<u64>,
+
+    /// Таймаут вызова в секундах, для server streaming - до конца потока
+    #[arg(short, long, default_value_t = 30)]
+    timeout: u64,
+
+    /// Остановить тест по условию, например "error_rate>5%" или "p99>800ms for 10s"
+    #[arg(long = "abort-on")]
+    abort_on: Vec<AbortCondition>,
+
+    /// Печатать строку на каждый вызов
+    #[arg(short, long)]
+    verbose: bool,
+
+    #[command(flatten)]
+    client: ClientOptions,
+}
+
+// Общие данные всех воркеров
+struct GrpcContext {
+    channels: Vec<Channel>,
+    path: PathAndQuery,
+    method: MethodDescriptor,
+    // Разобранный заранее запрос, если в шаблоне нет подстановок
+    request: Option<DynamicMessage>,
+    template: String,
+    metadata: MetadataMap,
+    auth: Option<Authorization>,
+    timeout: Duration,
+    url: Arc<str>,
+    jobs: JobSource,
+    verbose: bool,
+    stop: StopSignal,
+    status_codes: [AtomicUsize; GRPC_CODES],
+    // Сообщений, полученных в ответ (для server streaming - по всем потокам)
+    responses: AtomicUsize,
+}
+
+pub(crate) async fn run(
+    config: GrpcConfig,
+    grace_period: Duration,
+    stop_handle: StopHandle,
+    stop: StopSignal,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if config.concurrency == 0 || config.connections == 0 {
+        return Err("Количество вызовов и соединений должно быть больше нуля".into());
+    }
+
+    // rustls не позволяет требовать TLS 1.3, а версии ниже 1.2 не использует никогда
+    client::reject_unsupported("grpc", &config.client, &[
+        ("--tls-min-version 1.3", matches!(config.client.tls.tls_min_version, Some(TlsVersion::Tls1_3))),
+    ])?;
+    let pool = load_descriptors(&config)?;
+    let method = find_method(&pool, &config.method)?;
+    if method.is_client_streaming() {
+        return Err(format!("Метод {} с потоком от клиента не поддерживается", method.full_name()).into());
+    }
+
+    let has_placeholders = ["{{userId}}", "{{timestamp}}", "{{uuid}}"]
+        .iter()
+        .any(|placeholder| config.data.contains(placeholder));
+    // Шаблон проверяется до начала теста, даже если разбирается на каждый вызов
+    let parsed = parse_request(&method.input(), &render(&config.data, 0))
+        .map_err(|e| format!("Некорректный запрос для {}: {}", method.input().full_name(), e))?;
+
+    let (jobs, scheduler) = load_jobs(
+        config.rps, config.total_requests, config.duration, config.concurrency, &stop,
+    )?;
+
+    let streaming = method.is_server_streaming();
+    println!("🧬 gRPC: {} ({})", method.full_name(), if streaming { "server streaming" } else { "unary" });
+    println!("🔗 URL: {}", config.url);
+    match config.rps {
+        Some(rps) => println!("📈 {} вызовов в секунду", rps),
+        None => println!("🚀 {} одновременных вызовов", config.concurrency),
+    }
+    if let Some(total) = config.total_requests {
+        println!("🔢 Всего вызовов: {}", total);
+    }
+    if let Some(duration) = config.duration {
+        println!("⏳ Длительность: {} секунд", duration);
+    }
+    println!("🔌 Соединений: {} ({})", config.connections, client::describe(&config.client));
+    println!("⏱️  Таймаут: {} секунд", config.timeout);
+    println!("📦 Запрос: {}", config.data);
+    if !config.abort_on.is_empty() {
+        println!("🛑 Условия остановки:");
+        for condition in &config.abort_on {
+            println!("  {}", condition);
+        }
+    }
+    println!("{}", "=".repeat(50));
+
+    let timeout = Duration::from_secs(config.timeout);
+    let endpoint = endpoint(&config, timeout)?;
+    let tcp = Arc::new(TcpConnector::new(&config.client)?);
+    let auth = match config.client.auth.config() {
+        Some(auth) => {
+            let token_client = client::build_client(&config.client)?.for_worker(0).clone();
+            Some(AuthProviders::new(token_client).get(&auth).await?)
+        }
+        None => None,
+    };
+    let workers = match config.rps {
+        Some(rps) => config.concurrency.max(rps * 2),
+        None => config.concurrency,
+    };
+    let path = format!("/{}/{}", method.parent_service().full_name(), method.name());
+
+    let context = Arc::new(GrpcContext {
+        // Соединения устанавливаются при первом вызове, ошибки попадают в отчет
+        // Каждое соединение идет со своего --local-address по очереди, как воркеры HTTP
+        channels: (0..config.connections)
+            .map(|connection| endpoint.connect_with_connector_lazy(GrpcConnector { tcp: tcp.clone(), connection }))
+            .collect(),
+        path: PathAndQuery::try_from(path).map_err(|e| format!("Некорректный метод: {}", e))?,
+        request: (!has_placeholders).then_some(parsed),
+        template: config.data,
+        metadata: metadata(&config.headers)?,
+        auth,
+        timeout,
+        url: Arc::from(config.url.as_str()),
+        method,
+        jobs,
+        verbose: config.verbose,
+        stop: stop.clone(),
+        status_codes: std::array::from_fn(|_| AtomicUsize::new(0)),
+        responses: AtomicUsize::new(0),
+    });
+
+    let (results_tx, results_rx) = mpsc::channel(workers * 2);
+    let guardrails = Guardrails::new(config.abort_on, stop_handle);
+    let aggregator = spawn_aggregator(results_rx, FAILURE_SAMPLES, config.rps.is_some(), guardrails);
+
+    let test_start = Instant::now();
+    let handles: Vec<JoinHandle<()>> = (0..workers)
+        .map(|worker| {
+            let context = context.clone();
+            let results = results_tx.clone();
+            tokio::spawn(async move {
+                let channel = &context.channels[worker % context.channels.len()];
+                while let Some(user_id) = context.jobs.next(&context.stop).await {
+                    let result = call(&context, channel.clone(), user_id).await;
+                    if results.send(result).await.is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(results_tx);
+
+    join_workers(handles, &stop, grace_period).await;
+    if let Some(scheduler) = scheduler
+        && let Err(e) = scheduler.await
+    {
+        eprintln!("Ошибка в задаче: {}", e);
+    }
+    let report = aggregator.await?;
+
+    println!("\n{}", "=".repeat(50));
+    println!("🎯 ИТОГИ ТЕСТА:");
+    print_stop_notice(&stop);
+    report.stats.print_summary();
+
+    println!("\n📈 Коды gRPC:");
+    for (code, count) in context.status_codes.iter().enumerate() {
+        let count = count.load(Ordering::Relaxed);
+        if count > 0 {
+            println!("  {:?} ({}): {}", Code::from(code as i32), code, count);
+        }
+    }
+    if streaming {
+        let responses = context.responses.load(Ordering::Relaxed);
+        let calls = report.stats.total_requests.max(1);
+        println!("📨 Сообщений в потоках: {} (в среднем {:.1} на вызов)",
+            responses, responses as f64 / calls as f64);
+    }
+
+    let total_duration = test_start.elapsed();
+    println!("\n⏰ Общее время теста: {:.2} секунд", total_duration.as_secs_f32());
+    println!("📊 Фактически вызовов в секунду: {:.1}",
+        report.stats.total_requests as f32 / total_duration.as_secs_f32());
+
+    report.print_failures();
+    Ok(())
+}
+
+// Набор дескрипторов из файла или от protoc
+fn load_descriptors(config: &GrpcConfig) -> Result<DescriptorPool, String> {
+    let bytes = match (&config.descriptor_set, &config.proto) {
+        (Some(path), _) => std::fs::read(path)
+            .map_err(|e| format!("Не удалось прочитать {}: {}", path.display(), e))?,
+        (None, Some(proto)) => compile_proto(proto, &config.import_paths)?,
+        (None, None) => return Err("Укажите --proto или --descriptor-set".to_string()),
+    };
+    DescriptorPool::decode(bytes.as_slice())
+        .map_err(|e| format!("Некорректный набор дескрипторов: {}", e))
+}
+
+fn compile_proto(proto: &Path, import_paths: &[PathBuf]) -> Result<Vec<u8>, String> {
+    let output = std::env::temp_dir().join(format!("load_test-{}.pb", std::process::id()));
+    let mut command = Command::new("protoc");
+    command
+        .arg("--include_imports")
+        .arg(format!("--descriptor_set_out={}", output.display()));
+    if import_paths.is_empty() {
+        let parent = proto.parent().filter(|parent| !parent.as_os_str().is_empty());
+        command.arg(format!("-I{}", parent.unwrap_or(Path::new(".")).display()));
+    }
+    for path in import_paths {
+        command.arg(format!("-I{}", path.display()));
+    }
+    command.arg(proto);
+
+    let status = command.output().map_err(|e| {
+        format!("Не удалось запустить protoc ({}); установите его или передайте --descriptor-set", e)
+    })?;
+    if !status.status.success() {
+        return Err(format!("protoc завершился с ошибкой:\n{}", String::from_utf8_lossy(&status.stderr).trim()));
+    }
+
+    let bytes = std::fs::read(&output).map_err(|e| format!("Не удалось прочитать вывод protoc: {}", e));
+    let _ = std::fs::remove_file(&output);
+    bytes
+}
+
+// Принимает package.Service/Method и package.Service.Method
+fn find_method(pool: &DescriptorPool, name: &str) -> Result<MethodDescriptor, String> {
+    let (service, method) = name
+        .rsplit_once('/')
+        .or_else(|| name.rsplit_once('.'))
+        .ok_or_else(|| format!("Ожидается метод в формате package.Service/Method: {}", name))?;
+    let service = pool
+        .get_service_by_name(service.trim_start_matches('/'))
+        .ok_or_else(|| format!("Сервис {} не найден в дескрипторах", service))?;
+    let found = service.methods().find(|candidate| candidate.name() == method);
+    found.ok_or_else(|| format!("Метод {} не найден в сервисе {}", method, service.full_name()))
+}
+
+fn render(template: &str, user_id: usize) -> String {
+    template
+        .replace("{{userId}}", &user_id.to_string())
+        .replace("{{timestamp}}", &Utc::now().to_rfc3339())
+        .replace("{{uuid}}", &uuid::Uuid::new_v4().to_string())
+}
+
+fn parse_request(descriptor: &MessageDescriptor, json: &str) -> Result<DynamicMessage, String> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let message = DynamicMessage::deserialize(descriptor.clone(), &mut deserializer)
+        .map_err(|e| e.to_string())?;
+    deserializer.end().map_err(|e| e.to_string())?;
+    Ok(message)
+}
+
+fn endpoint(config: &GrpcConfig, timeout: Duration) -> Result<Endpoint, String> {
+    let options = &config.client;
+    let mut endpoint = Endpoint::from_shared(config.url.clone())
+        .map_err(|e| format!("Некорректный URL: {}", e))?
+        .connect_timeout(options.connect_timeout.map_or(timeout, Duration::from_secs))
+        .tcp_nodelay(options.tcp_nodelay);
+
+    let tls = &options.tls;
+    if !config.url.starts_with("https://") {
+        if tls.cacert.is_some() || tls.cert.is_some() || tls.insecure || tls.tls_server_name.is_some() {
+            return Err("--cacert, --cert, --insecure и --tls-server-name требуют адрес https://".to_string());
+        }
+        return Ok(endpoint);
+    }
+
+    let mut tls_config = ClientTlsConfig::new();
+    if let Some(files) = IdentityFiles::read(tls)? {
+        match files {
+            IdentityFiles::Pem { cert, key, .. } => tls_config = tls_config.identity(Identity::from_pem(cert, key)),
+            IdentityFiles::Pkcs12 { path, .. } => {
+                return Err(format!("grpc принимает клиентский сертификат только в PEM (--cert и --key): {}", path));
+            }
+        }
+    }
+    if let Some(name) = &tls.tls_server_name {
+        tls_config = tls_config.domain_name(name.clone());
+    }
+
+    // С --insecure корневые сертификаты не нужны: проверку заменяет AcceptAnyCertificate
+    endpoint = if tls.insecure {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        endpoint.tls_config_with_verifier(tls_config, Arc::new(AcceptAnyCertificate(provider)))
+    } else {
+        tls_config = tls_config.with_native_roots();
+        if let Some(path) = &tls.cacert {
+            let pem = std::fs::read(path)
+                .map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?;
+            tls_config = tls_config.ca_certificate(Certificate::from_pem(pem));
+        }
+        endpoint.tls_config(tls_config)
+    }
+    .map_err(|e| format!("Ошибка настройки TLS: {}", e))?;
+    Ok(endpoint)
+}
+
+// Соединения каналов tonic через общий TcpConnector: --resolve, --dns-spread, --proxy, --local-address.
+// TLS поверх этого соединения устанавливает сам tonic.
+#[derive(Clone)]
+struct GrpcConnector {
+    tcp: Arc<TcpConnector>,
+    connection: usize,
+}
+
+impl Service<Uri> for GrpcConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let (tcp, connection) = (self.tcp.clone(), self.connection);
+        Box::pin(async move {
+            let host = uri.host()
+                .ok_or_else(|| std::io::Error::other(format!("В URL нет хоста: {}", uri)))?
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string();
+            let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("https") { 443 } else { 80 });
+            tcp.connect(&host, port, connection).await.map(TokioIo::new)
+        })
+    }
+}
+
+// Проверка сервера для --insecure: сертификат и имя принимаются любые,
+// но подписи рукопожатия проверяются, иначе TLS не установится
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn metadata(headers: &[String]) -> Result<MetadataMap, String> {
+    let mut metadata = MetadataMap::new();
+    for header in headers {
+        let (name, value) = header.split_once(':')
+            .ok_or_else(|| format!("Некорректный заголовок: {}", header))?;
+        let key = MetadataKey::from_bytes(name.trim().to_lowercase().as_bytes())
+            .map_err(|e| format!("Некорректное имя метаданных {}: {}", name, e))?;
+        let value = MetadataValue::try_from(value.trim())
+            .map_err(|e| format!("Некорректное значение метаданных {}: {}", name, e))?;
+        metadata.append(key, value);
+    }
+    Ok(metadata)
+}
+
+async fn call(context: &GrpcContext, channel: Channel, user_id: usize) -> RequestResult {
+    let start = Instant::now();
+    let outcome = match &context.request {
+        Some(message) => Ok(message.clone()),
+        None => parse_request(&context.method.input(), &render(&context.template, user_id))
+            .map_err(|e| Status::invalid_argument(format!("Некорректный запрос: {}", e))),
+    };
+    let outcome = match outcome {
+        Ok(message) => tokio::time::timeout(context.timeout, send(context, channel, message))
+            .await
+            .unwrap_or_else(|_| Err(Status::deadline_exceeded("Таймаут вызова"))),
+        Err(status) => Err(status),
+    };
+    let duration = start.elapsed();
+    // tonic отменяет вызов по grpc-timeout со статусом Cancelled; по спецификации
+    // истекший срок на клиенте - DEADLINE_EXCEEDED
+    let outcome = outcome.map_err(|status| match status.code() {
+        Code::Cancelled if duration >= context.timeout => Status::deadline_exceeded("Таймаут вызова"),
+        _ => status,
+    });
+
+    let code = outcome.as_ref().map_or_else(|status| status.code(), |_| Code::Ok);
+    if let Some(count) = context.status_codes.get(code as usize) {
+        count.fetch_add(1, Ordering::Relaxed);
+    }
+    if let Ok(responses) = &outcome {
+        context.responses.fetch_add(*responses, Ordering::Relaxed);
+    }
+
+    if context.verbose {
+        match &outcome {
+            Ok(responses) => println!("👤 {} {} ✅ {:.2}ms, ответов: {}",
+                user_id, context.method.name(), duration.as_millis(), responses),
+            Err(status) => println!("👤 {} {} ❌ {:?}: {} {:.2}ms",
+                user_id, context.method.name(), code, status.message(), duration.as_millis()),
+        }
+    }
+
+    RequestResult {
+        user_id,
+        success: outcome.is_ok(),
+        duration,
+        // Коды gRPC считаются отдельно, включая неудачные вызовы
+        status_code: None,
+        error_class: outcome.as_ref().err().map(classify_status),
+        error: outcome.as_ref().err().map(|status| format!("{:?}: {}", status.code(), status.message())),
+        url: context.url.clone(),
+        redirects: 0,
+        final_url: None,
+        stream: None,
+    }
+}
+
+// Отправляет вызов и возвращает число полученных сообщений
+async fn send(context: &GrpcContext, channel: Channel, message: DynamicMessage) -> Result<usize, Status> {
+    let mut request = tonic::Request::new(message);
+    *request.metadata_mut() = context.metadata.clone();
+    if let Some(auth) = &context.auth
+        && let Ok(value) = MetadataValue::try_from(auth.header().as_bytes())
+    {
+        request.metadata_mut().insert("authorization", value);
+    }
+    request.set_timeout(context.timeout);
+
+    let mut client = tonic::client::Grpc::new(channel);
+    client.ready().await.map_err(|e| Status::from_error(e.into()))?;
+    let codec = DynamicCodec { response: context.method.output() };
+
+    if context.method.is_server_streaming() {
+        let mut stream = client.server_streaming(request, context.path.clone(), codec).await?.into_inner();
+        let mut received = 0;
+        while stream.message().await?.is_some() {
+            received += 1;
+        }
+        Ok(received)
+    } else {
+        client.unary(request, context.path.clone(), codec).await?;
+        Ok(1)
+    }
+}
+
+// Ошибки транспорта приходят как Status с причиной; статусы сервера - без нее
+fn classify_status(status: &Status) -> ErrorClass {
+    if status.code() == Code::DeadlineExceeded {
+        return ErrorClass::Timeout;
+    }
+    let Some(source) = std::error::Error::source(status) else {
+        return ErrorClass::Grpc;
+    };
+    classify_transport(source).unwrap_or(ErrorClass::Connect)
+}
+
+// Кодек tonic для сообщений, описанных дескрипторами во время выполнения
+struct DynamicCodec {
+    response: MessageDescriptor,
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder { response: self.response.clone() }
+    }
+}
+
+struct DynamicEncoder;
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst).map_err(|e| Status::internal(format!("Ошибка кодирования запроса: {}", e)))
+    }
+}
+
+struct DynamicDecoder {
+    response: MessageDescriptor,
+}
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.response.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(format!("Ошибка декодирования ответа: {}", e)))
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::VecDeque;
+
+use load_test::{AbortCondition, AbortMetric};
+
+use crate::shutdown::{StopHandle, StopReason};
+use crate::stats::{LatencyHistogram, RequestResult};
+
+// Результаты одной секунды теста для скользящего окна
+#[derive(Debug, Default)]
+struct WindowBucket {
+    total: usize,
+    failed: usize,
+    // Время ответа всех запросов, включая неудачные (таймауты тоже важны)
+    latency: LatencyHistogram,
+}
+
+// Проверяет условия досрочной остановки раз в секунду по скользящему окну
+pub(crate) struct Guardrails {
+    conditions: Vec<AbortCondition>,
+    stop: StopHandle,
+    current: WindowBucket,
+    // Завершенные секунды, последняя - в конце
+    window: VecDeque<WindowBucket>,
+    window_len: usize,
+    triggered: bool,
+}
+
+impl Guardrails {
+    pub(crate) fn new(conditions: Vec<AbortCondition>, stop: StopHandle) -> Option<Self> {
+        if conditions.is_empty() {
+            return None;
+        }
+
+        let window_len = conditions
+            .iter()
+            .map(window_seconds)
+            .max()
+            .unwrap_or(1);
+
+        Some(Self {
+            conditions,
+            stop,
+            current: WindowBucket::default(),
+            window: VecDeque::with_capacity(window_len),
+            window_len,
+            triggered: false,
+        })
+    }
+
+    pub(crate) fn add_result(&mut self, result: &RequestResult) {
+        self.current.total += 1;
+        if !result.success {
+            self.current.failed += 1;
+        }
+        self.current.latency.record(result.duration);
+    }
+
+    // Закрывает текущую секунду и проверяет условия
+    pub(crate) fn on_second(&mut self) {
+        self.window.push_back(std::mem::take(&mut self.current));
+        if self.window.len() > self.window_len {
+            self.window.pop_front();
+        }
+
+        if self.triggered {
+            return;
+        }
+
+        for condition in &self.conditions {
+            if let Some(observed) = self.check(condition) {
+                let reason = format!("{} (фактически {})", condition, observed);
+                eprintln!("\n🛑 Сработало условие остановки: {}", reason);
+
+                self.triggered = true;
+                self.stop.stop(StopReason::AbortCondition(reason));
+                return;
+            }
+        }
+    }
+
+    // Возвращает наблюдаемое значение, если условие выполнено
+    fn check(&self, condition: &AbortCondition) -> Option<String> {
+        let seconds = window_seconds(condition);
+        // Окно еще не заполнено
+        if self.window.len() < seconds {
+            return None;
+        }
+
+        let mut total = 0;
+        let mut failed = 0;
+        let mut latency = LatencyHistogram::default();
+        for bucket in self.window.iter().rev().take(seconds) {
+            total += bucket.total;
+            failed += bucket.failed;
+            latency.merge(&bucket.latency);
+        }
+        if total == 0 {
+            return None;
+        }
+
+        match &condition.metric {
+            AbortMetric::ErrorRate(limit) => {
+                let error_rate = failed as f64 / total as f64 * 100.0;
+                (error_rate > *limit).then(|| format!("{:.1}%", error_rate))
+            }
+            AbortMetric::Latency { percentile, limit } => {
+                if latency.is_empty() {
+                    return None;
+                }
+                let observed = latency.percentile(*percentile);
+                (observed > *limit).then(|| format!("{}ms", observed.as_millis()))
+            }
+        }
+    }
+}
+
+fn window_seconds(condition: &AbortCondition) -> usize {
+    (condition.window.as_secs_f64().ceil() as usize).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::shutdown::{StopSignal, stop_channel};
+
+    fn guardrails(conditions: &[&str]) -> (Guardrails, StopSignal) {
+        let conditions = conditions.iter().map(|condition| condition.parse().unwrap()).collect();
+        let (stop, signal) = stop_channel();
+        (Guardrails::new(conditions, stop).unwrap(), signal)
+    }
+
+    fn result(success: bool, millis: u64) -> RequestResult {
+        RequestResult {
+            user_id: 0,
+            success,
+            duration: Duration::from_millis(millis),
+            status_code: Some(if success { 200 } else { 500 }),
+            error: None,
+            error_class: None,
+            url: Arc::from("http://localhost/"),
+            redirects: 0,
+            final_url: None,
+            stream: None,
+        }
+    }
+
+    // Одна секунда теста: total запросов, из них failed неудачных
+    fn second(guardrails: &mut Guardrails, total: usize, failed: usize, millis: u64) {
+        for index in 0..total {
+            guardrails.add_result(&result(index >= failed, millis));
+        }
+        guardrails.on_second();
+    }
+
+    #[test]
+    fn no_conditions_no_guardrails() {
+        let (stop, _signal) = stop_channel();
+        assert!(Guardrails::new(Vec::new(), stop).is_none());
+    }
+
+    #[test]
+    fn error_rate_trips_after_full_window() {
+        let (mut guardrails, signal) = guardrails(&["error_rate>20% for 3s"]);
+        for _ in 0..2 {
+            second(&mut guardrails, 10, 5, 10);
+            assert!(!signal.is_stopped());
+        }
+        second(&mut guardrails, 10, 5, 10);
+        let reason = signal.reason().unwrap().to_string();
+        assert!(reason.contains("error_rate>20% for 3s"), "{}", reason);
+        assert!(reason.contains("50.0%"), "{}", reason);
+    }
+
+    #[test]
+    fn breach_shorter_than_window_does_not_trip() {
+        let (mut guardrails, signal) = guardrails(&["error_rate>30% for 5s"]);
+        // Секунда сплошных ошибок среди нормальных дает 20% по окну
+        for failed in [0, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0] {
+            second(&mut guardrails, 10, failed, 10);
+            assert!(!signal.is_stopped());
+        }
+        // Две секунды ошибок подряд - уже 40%
+        second(&mut guardrails, 10, 10, 10);
+        assert!(!signal.is_stopped());
+        second(&mut guardrails, 10, 10, 10);
+        assert!(signal.reason().unwrap().to_string().contains("40.0%"));
+    }
+
+    #[test]
+    fn latency_uses_default_window() {
+        let (mut guardrails, signal) = guardrails(&["p99>5s"]);
+        // Медленные ответы с начала теста: пока окно в 5 секунд не заполнено, условие не проверяется
+        for _ in 0..4 {
+            second(&mut guardrails, 10, 0, 6000);
+            assert!(!signal.is_stopped());
+        }
+        second(&mut guardrails, 10, 0, 6000);
+        let reason = signal.reason().unwrap().to_string();
+        assert!(reason.contains("p99>5s"), "{}", reason);
+    }
+
+    #[test]
+    fn latency_below_limit_does_not_trip() {
+        let (mut guardrails, signal) = guardrails(&["p99>5s"]);
+        for _ in 0..10 {
+            second(&mut guardrails, 100, 0, 4900);
+        }
+        assert!(!signal.is_stopped());
+    }
+
+    #[test]
+    fn empty_seconds_do_not_trip() {
+        let (mut guardrails, signal) = guardrails(&["error_rate>0% for 2s"]);
+        for _ in 0..5 {
+            second(&mut guardrails, 0, 0, 0);
+        }
+        assert!(!signal.is_stopped());
+    }
+}
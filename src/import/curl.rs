@@ -0,0 +1,598 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose, Engine as _};
+use clap::{Args, ValueEnum};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use load_test::{BodyKind, HttpMethod, MultiTestConfig, TlsOptions, UrlConfig};
+
+use super::write_config;
+use crate::{BodyType, RequestConfig};
+
+// Заголовок Accept-Encoding, который curl отправляет с --compressed
+const COMPRESSED_ENCODINGS: &str = "deflate, gzip, br, zstd";
+pub(super) const FORM_BOUNDARY: &str = "------------------------load-test-form";
+// --data-urlencode оставляет без кодирования только незарезервированные символы, пробел - %20
+const URLENCODE_DATA: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+#[derive(Args)]
+pub(crate) struct CurlImport {
+    /// Файл с командами curl; команды разделяются пустой строкой или начинаются с curl
+    pub file: String,
+
+    /// Куда сохранить конфигурацию (.json, .yaml, .toml); без флага - YAML в stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+// Запрос, разобранный из команды curl. Заголовки хранятся в том виде, в каком их принимает -H;
+// body - байты, которые отправил бы curl: текст данных -d или собранный multipart
+#[derive(Debug)]
+pub(crate) struct CurlRequest {
+    pub url: String,
+    pub method: HttpMethod,
+    pub headers: Vec<String>,
+    pub body: Option<BodyType>,
+    // -k: не проверять сертификат
+    pub insecure: bool,
+    // -m: общий таймаут запроса, секунды
+    pub timeout: Option<u64>,
+}
+
+impl CurlRequest {
+    pub(crate) fn parse(command: &str) -> Result<Self, String> {
+        let words = split_words(command)?;
+        let mut words = words.into_iter();
+        match words.next().as_deref() {
+            Some("curl" | "curl.exe") => {}
+            _ => return Err("Команда должна начинаться с curl".to_string()),
+        }
+
+        let mut url = None;
+        let mut method = None;
+        let mut headers: Vec<String> = Vec::new();
+        let mut data: Vec<String> = Vec::new();
+        let mut form: Vec<String> = Vec::new();
+        let mut head = false;
+        let mut get = false;
+        let mut compressed = false;
+        let mut insecure = false;
+        let mut timeout = None;
+
+        while let Some(word) = words.next() {
+            if !word.starts_with('-') || word == "-" {
+                if url.replace(word.clone()).is_some() {
+                    return Err(format!("Указано несколько URL: {}", word));
+                }
+                continue;
+            }
+
+            // Короткий параметр со значением может быть записан слитно: -XPOST
+            let (option, attached) = match word.strip_prefix('-') {
+                Some(short) if !short.starts_with('-') && short.len() > 1
+                    && word.is_char_boundary(2) && takes_value(&word[..2]) => {
+                    (word[..2].to_string(), Some(word[2..].to_string()))
+                }
+                _ => (word.clone(), None),
+            };
+            let mut value = || attached.clone().or_else(|| words.next())
+                .ok_or_else(|| format!("Не указано значение для {}", option));
+
+            match option.as_str() {
+                "-X" | "--request" => {
+                    let name = value()?;
+                    method = Some(HttpMethod::from_str(&name, true)
+                        .map_err(|_| format!("Неподдерживаемый метод {}", name))?);
+                }
+                "-H" | "--header" => headers.push(header(&value()?)?),
+                "-d" | "--data" | "--data-ascii" => data.push(read_data(&value()?, true)?),
+                "--data-binary" => data.push(read_data(&value()?, false)?),
+                "--data-raw" => data.push(value()?),
+                "--data-urlencode" => data.push(urlencode_data(&value()?)?),
+                "-F" | "--form" => form.push(value()?),
+                "-u" | "--user" => {
+                    let credentials = value()?;
+                    let credentials = if credentials.contains(':') { credentials } else { format!("{}:", credentials) };
+                    headers.push(format!("Authorization: Basic {}", general_purpose::STANDARD.encode(credentials)));
+                }
+                "-b" | "--cookie" => {
+                    let cookie = value()?;
+                    if !cookie.contains('=') {
+                        return Err(format!("Файлы cookie не поддерживаются: {}", cookie));
+                    }
+                    headers.push(format!("Cookie: {}", cookie));
+                }
+                "-A" | "--user-agent" => headers.push(format!("User-Agent: {}", value()?)),
+                "-e" | "--referer" => headers.push(format!("Referer: {}", value()?)),
+                "-m" | "--max-time" => {
+                    let seconds = value()?;
+                    let seconds: f64 = seconds.parse()
+                        .map_err(|_| format!("Некорректный --max-time: {}", seconds))?;
+                    timeout = Some(seconds.ceil().max(1.0) as u64);
+                }
+                "--url" => {
+                    if url.replace(value()?).is_some() {
+                        return Err("Указано несколько URL".to_string());
+                    }
+                }
+                "--compressed" => compressed = true,
+                "-k" | "--insecure" => insecure = true,
+                "-I" | "--head" => head = true,
+                "-G" | "--get" => get = true,
+                // Не влияют на запрос
+                "-o" | "--output" | "--connect-timeout" => { value()?; }
+                "-s" | "--silent" | "-S" | "--show-error" | "-L" | "--location" | "-i" | "--include"
+                | "-v" | "--verbose" | "-g" | "--globoff" | "-N" | "--no-buffer" | "-f" | "--fail"
+                | "--http1.1" | "--http2" | "--http2-prior-knowledge" => {}
+                // Несколько флагов без значений вместе: -sSL
+                _ if !option.starts_with("--") && option.len() > 2 => {
+                    for flag in option[1..].chars() {
+                        match flag {
+                            'k' => insecure = true,
+                            'I' => head = true,
+                            'G' => get = true,
+                            's' | 'S' | 'L' | 'i' | 'v' | 'g' | 'N' | 'f' => {}
+                            _ => return Err(format!("Неподдерживаемый параметр curl: -{}", flag)),
+                        }
+                    }
+                }
+                _ => return Err(format!("Неподдерживаемый параметр curl: {}", option)),
+            }
+        }
+
+        let mut url = url.ok_or("В команде curl не указан URL")?;
+        if !url.contains("://") {
+            url = format!("http://{}", url);
+        }
+        if !data.is_empty() && !form.is_empty() {
+            return Err("Нельзя одновременно указать -d и -F".to_string());
+        }
+
+        let has_header = |headers: &[String], name: &str| headers.iter()
+            .any(|header| header.split(':').next().is_some_and(|key| key.trim().eq_ignore_ascii_case(name)));
+        if compressed && !has_header(&headers, "Accept-Encoding") {
+            headers.push(format!("Accept-Encoding: {}", COMPRESSED_ENCODINGS));
+        }
+
+        let data = (!data.is_empty()).then(|| data.join("&"));
+        let mut body = None;
+        if get {
+            // -G: данные уходят в строку запроса
+            if let Some(data) = data {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                url = format!("{}{}{}", url, separator, data);
+            }
+        } else if let Some(data) = data {
+            if !has_header(&headers, "Content-Type") {
+                headers.push("Content-Type: application/x-www-form-urlencoded".to_string());
+            }
+            body = Some(BodyType::Text(data));
+        } else if !form.is_empty() {
+            if !has_header(&headers, "Content-Type") {
+                headers.push(format!("Content-Type: multipart/form-data; boundary={}", FORM_BOUNDARY));
+            }
+            let parts = form.iter().map(|field| FormPart::parse(field)).collect::<Result<Vec<_>, _>>()?;
+            body = Some(BodyType::Binary(multipart_body(&parts)?));
+        }
+
+        let method = match method {
+            Some(method) => method,
+            None if head => HttpMethod::HEAD,
+            None if body.is_some() => HttpMethod::POST,
+            None => HttpMethod::GET,
+        };
+
+        Ok(Self { url, method, headers, body, insecure, timeout })
+    }
+
+    pub(crate) fn request_config(&self, timeout_secs: u64) -> Result<RequestConfig, String> {
+        let mut config = RequestConfig::from_cli(
+            self.url.clone(),
+            self.method.clone(),
+            None,
+            self.headers.clone(),
+            self.timeout.unwrap_or(timeout_secs),
+            None,
+        )?;
+        if let Some(body) = &self.body {
+            config.body = body.clone();
+        }
+        Ok(config)
+    }
+
+    // Body в виде строки для -d или поля body и тип, с которым ее нужно читать
+    pub(crate) fn body_field(&self) -> (Option<String>, BodyKind) {
+        match &self.body {
+            Some(BodyType::Text(text)) => (Some(text.clone()), BodyKind::Text),
+            Some(BodyType::Binary(data)) => (Some(general_purpose::STANDARD.encode(data)), BodyKind::Base64),
+            _ => (None, BodyKind::Auto),
+        }
+    }
+
+    // Та же команда в виде параметров load_test check
+    pub(crate) fn check_invocation(&self) -> String {
+        let method = self.method.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        let mut parts = vec![
+            "load_test check".to_string(),
+            format!("-U {}", quote(&self.url)),
+            format!("-X {}", method),
+        ];
+        parts.extend(self.headers.iter().map(|header| format!("-H {}", quote(header))));
+        if let (Some(body), body_kind) = self.body_field() {
+            let body_kind = body_kind.to_possible_value()
+                .map(|value| value.get_name().to_string())
+                .unwrap_or_default();
+            parts.push(format!("--body-type {} -d {}", body_kind, quote(&body)));
+        }
+        parts.join(" ")
+    }
+
+    fn url_config(self) -> UrlConfig {
+        let (body, body_kind) = self.body_field();
+        let headers: HashMap<String, String> = self.headers.iter()
+            .filter_map(|header| header.split_once(':'))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        UrlConfig {
+            url: self.url,
+            method: Some(self.method),
+            body_type: body.is_some().then_some(body_kind),
+            body,
+            headers: (!headers.is_empty()).then_some(headers),
+            weight: None,
+            timeout: self.timeout,
+            tls: self.insecure.then(|| TlsOptions { insecure: true, ..Default::default() }),
+            auth: None,
+            sign: None,
+            graphql: None,
+            stream: None,
+            socket_path: None,
+            think_time_ms: None,
+            group: None,
+        }
+    }
+}
+
+pub(crate) fn run(args: CurlImport) -> Result<(), String> {
+    let content = std::fs::read_to_string(&args.file)
+        .map_err(|e| format!("Не удалось прочитать {}: {}", args.file, e))?;
+
+    let mut urls = Vec::new();
+    for (index, command) in split_commands(&content).iter().enumerate() {
+        let request = CurlRequest::parse(command)
+            .map_err(|e| format!("Команда {}: {}", index + 1, e))?;
+        urls.push(request.url_config());
+    }
+    if urls.is_empty() {
+        return Err(format!("В {} нет команд curl", args.file));
+    }
+    eprintln!("📥 Перенесено команд curl: {}", urls.len());
+
+    let config = MultiTestConfig {
+        urls,
+        rps: None,
+        duration_seconds: None,
+        ..Default::default()
+    };
+    write_config(&config, args.output.as_deref())
+}
+
+// Делит файл на команды: новая команда начинается со строки curl или после пустой строки.
+// Строки с # в начале пропускаются.
+fn split_commands(content: &str) -> Vec<String> {
+    let mut commands: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        let starts_command = trimmed.starts_with("curl ") || trimmed.starts_with("curl.exe ");
+        if trimmed.is_empty() || starts_command {
+            if !current.trim().is_empty() {
+                commands.push(std::mem::take(&mut current));
+            }
+            current.clear();
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        commands.push(current);
+    }
+    commands
+}
+
+fn takes_value(option: &str) -> bool {
+    matches!(option, "-X" | "-H" | "-d" | "-F" | "-u" | "-b" | "-A" | "-e" | "-m" | "-o")
+}
+
+fn header(value: &str) -> Result<String, String> {
+    // "Name;" в curl - заголовок с пустым значением
+    if let Some(name) = value.strip_suffix(';')
+        && !name.contains(':')
+    {
+        return Ok(format!("{}:", name));
+    }
+    if !value.contains(':') {
+        return Err(format!("Некорректный заголовок: {}", value));
+    }
+    Ok(value.to_string())
+}
+
+// -d @file читает файл без переводов строк, --data-binary @file - как есть
+fn read_data(value: &str, strip_newlines: bool) -> Result<String, String> {
+    let Some(path) = value.strip_prefix('@') else { return Ok(value.to_string()) };
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?;
+    Ok(if strip_newlines { content.replace(['\r', '\n'], "") } else { content })
+}
+
+// --data-urlencode: name=content кодирует только content, без имени - все значение
+fn urlencode_data(value: &str) -> Result<String, String> {
+    let encode = |content: &str| utf8_percent_encode(content, URLENCODE_DATA).to_string();
+    let (name, content) = match value.split_once('=') {
+        Some((name, content)) => (Some(name), content.to_string()),
+        None => match value.split_once('@') {
+            Some((name, path)) => (Some(name).filter(|name| !name.is_empty()), read_data(&format!("@{}", path), false)?),
+            None => (None, value.to_string()),
+        },
+    };
+    Ok(match name {
+        Some(name) => format!("{}={}", name, encode(&content)),
+        None => encode(&content),
+    })
+}
+
+// Часть multipart/form-data
+pub(super) enum FormPart {
+    // Значение отправляется как есть, без разбора @ и <
+    Text { name: String, value: Vec<u8> },
+    File { name: String, path: String, content_type: String },
+}
+
+impl FormPart {
+    // Параметр -F: name=value, name=@file;type=..., name=<file (содержимое файла как значение)
+    fn parse(field: &str) -> Result<Self, String> {
+        let (name, value) = field.split_once('=')
+            .ok_or_else(|| format!("Некорректное поле формы: {}", field))?;
+        let name = name.to_string();
+        if let Some(file) = value.strip_prefix('@') {
+            let mut parts = file.split(';');
+            let path = parts.next().unwrap_or_default().to_string();
+            let content_type = parts.find_map(|part| part.strip_prefix("type="))
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            return Ok(Self::File { name, path, content_type });
+        }
+        let value = match value.strip_prefix('<') {
+            Some(path) => std::fs::read(path).map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?,
+            None => value.as_bytes().to_vec(),
+        };
+        Ok(Self::Text { name, value })
+    }
+}
+
+// Собирает multipart/form-data; файлы читаются с диска
+pub(super) fn multipart_body(parts: &[FormPart]) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    for part in parts {
+        body.extend(format!("--{}\r\n", FORM_BOUNDARY).as_bytes());
+        match part {
+            FormPart::File { name, path, content_type } => {
+                let content = std::fs::read(path)
+                    .map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?;
+                let file_name = std::path::Path::new(path).file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                body.extend(format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                    name, file_name, content_type
+                ).as_bytes());
+                body.extend(content);
+            }
+            FormPart::Text { name, value } => {
+                body.extend(format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name).as_bytes());
+                body.extend(value);
+            }
+        }
+        body.extend(b"\r\n");
+    }
+    body.extend(format!("--{}--\r\n", FORM_BOUNDARY).as_bytes());
+    Ok(body)
+}
+
+// Разбивает строку на слова по правилам sh: кавычки '...', "...", $'...',
+// экранирование \ и перенос строки через \ в конце строки
+fn split_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => { chars.next(); }
+                Some(next) => { current.push(next); in_word = true; }
+                None => {}
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(next) => current.push(next),
+                        None => return Err("Незакрытая кавычка '".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(next @ ('"' | '\\' | '$' | '`')) => current.push(next),
+                            Some('\n') => {}
+                            Some(next) => { current.push('\\'); current.push(next); }
+                            None => return Err("Незакрытая кавычка \"".to_string()),
+                        },
+                        Some(next) => current.push(next),
+                        None => return Err("Незакрытая кавычка \"".to_string()),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                ansi_c_string(&mut chars, &mut current)?;
+            }
+            c => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+// Строка $'...' с экранированием в стиле C; так DevTools записывает body со спецсимволами
+fn ansi_c_string(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, out: &mut String) -> Result<(), String> {
+    let unclosed = || "Незакрытая кавычка $'".to_string();
+    loop {
+        match chars.next().ok_or_else(unclosed)? {
+            '\'' => return Ok(()),
+            '\\' => {
+                let escaped = chars.next().ok_or_else(unclosed)?;
+                match escaped {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    '0' => out.push('\0'),
+                    'x' | 'u' | 'U' => {
+                        let max_digits = match escaped { 'x' => 2, 'u' => 4, _ => 8 };
+                        let mut digits = String::new();
+                        while digits.len() < max_digits
+                            && let Some(&digit) = chars.peek()
+                            && digit.is_ascii_hexdigit()
+                        {
+                            digits.push(digit);
+                            chars.next();
+                        }
+                        let code = u32::from_str_radix(&digits, 16)
+                            .map_err(|_| format!("Некорректная последовательность \\{}{}", escaped, digits))?;
+                        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    other => out.push(other),
+                }
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+// Значение для командной строки sh: в одинарных кавычках, если нужно
+fn quote(value: &str) -> String {
+    let plain = !value.is_empty() && value.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_body, parse_body_as};
+
+    // Байты body после сохранения в конфигурацию и загрузки multi
+    fn sent_body(command: &str) -> Vec<u8> {
+        let url_config = CurlRequest::parse(command).unwrap().url_config();
+        let yaml = serde_yaml::to_string(&url_config).unwrap();
+        let loaded: UrlConfig = serde_yaml::from_str(&yaml).unwrap();
+        let body = parse_body_as(&loaded.body.unwrap(), loaded.body_type.unwrap_or_default()).unwrap();
+        encode_body(&body).0.unwrap().to_vec()
+    }
+
+    #[test]
+    fn parses_method_headers_and_flags() {
+        let request = CurlRequest::parse(
+            "curl -XPUT 'https://api.example.com/items/1' -H 'Accept: application/json' -sSk --compressed -m 2.5",
+        ).unwrap();
+        assert_eq!(request.url, "https://api.example.com/items/1");
+        assert_eq!(request.method, HttpMethod::PUT);
+        assert!(request.insecure);
+        assert_eq!(request.timeout, Some(3));
+        assert_eq!(request.headers, vec![
+            "Accept: application/json".to_string(),
+            format!("Accept-Encoding: {}", COMPRESSED_ENCODINGS),
+        ]);
+        assert!(request.body.is_none());
+    }
+
+    #[test]
+    fn data_defaults_to_post_form() {
+        let request = CurlRequest::parse("curl example.com/login -d user=admin").unwrap();
+        assert_eq!(request.url, "http://example.com/login");
+        assert_eq!(request.method, HttpMethod::POST);
+        assert!(request.headers.contains(&"Content-Type: application/x-www-form-urlencoded".to_string()));
+        assert!(matches!(request.body, Some(BodyType::Text(ref text)) if text == "user=admin"));
+    }
+
+    #[test]
+    fn get_moves_data_to_query() {
+        let request = CurlRequest::parse("curl -G 'http://host/search?a=1' --data-urlencode 'q=a b'").unwrap();
+        assert_eq!(request.url, "http://host/search?a=1&q=a%20b");
+        assert_eq!(request.method, HttpMethod::GET);
+        assert!(request.body.is_none());
+    }
+
+    #[test]
+    fn data_is_sent_as_curl_sends_it() {
+        assert_eq!(sent_body("curl http://host -d test"), b"test");
+        assert_eq!(sent_body("curl http://host --data-raw abcd -H 'Content-Type: text/plain'"), b"abcd");
+        assert_eq!(sent_body("curl http://host -d 'q=a%20b'"), b"q=a%20b");
+        assert_eq!(sent_body("curl http://host --data-urlencode 'q=hello world&x'"), b"q=hello%20world%26x");
+        assert_eq!(sent_body("curl http://host -d b=2 -d a=1"), b"b=2&a=1");
+        assert_eq!(sent_body(r#"curl http://host --data-raw '{"id": 1}'"#), br#"{"id": 1}"#);
+    }
+
+    #[test]
+    fn form_is_sent_as_multipart_bytes() {
+        let body = sent_body("curl http://host -F name=value");
+        let expected = format!(
+            "--{0}\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nvalue\r\n--{0}--\r\n",
+            FORM_BOUNDARY,
+        );
+        assert_eq!(body, expected.as_bytes());
+    }
+
+    #[test]
+    fn check_invocation_keeps_body_type() {
+        let request = CurlRequest::parse("curl http://host -d test").unwrap();
+        assert!(request.check_invocation().ends_with("--body-type text -d test"));
+    }
+
+    #[test]
+    fn rejects_unsupported_options() {
+        assert!(CurlRequest::parse("wget http://host").is_err());
+        assert!(CurlRequest::parse("curl http://host --unknown").is_err());
+        assert!(CurlRequest::parse("curl http://host -d a -F b=c").is_err());
+        assert!(CurlRequest::parse("curl 'http://host").is_err());
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, FixedOffset};
+use clap::{Args, ValueEnum};
+use load_test::{BodyKind, HttpMethod, MultiTestConfig, UrlConfig};
+use serde::Deserialize;
+use url::Url;
+
+use super::{keep_header, write_config};
+
+#[derive(Args)]
+pub(crate) struct HarImport {
+    /// Файл HAR
+    pub file: String,
+
+    /// Куда сохранить конфигурацию (.json, .yaml, .toml); без флага - YAML в stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Только запросы к этим доменам и их поддоменам (можно указать несколько)
+    #[arg(long, value_delimiter = ',')]
+    pub domain: Vec<String>,
+
+    /// Только ответы с этими типами содержимого, по префиксу: application/json, text/ (можно указать несколько)
+    #[arg(long, value_delimiter = ',')]
+    pub content_type: Vec<String>,
+
+    /// Ограничить паузы между запросами, мс
+    #[arg(long)]
+    pub max_think_time: Option<u64>,
+
+    /// Не переносить паузы между запросами
+    #[arg(long, conflicts_with = "max_think_time")]
+    pub no_think_time: bool,
+}
+
+#[derive(Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Deserialize)]
+struct HarLog {
+    #[serde(default)]
+    entries: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    started_date_time: String,
+    // Полное время запроса, мс
+    #[serde(default)]
+    time: f64,
+    request: Request,
+    #[serde(default)]
+    response: Option<Response>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<Param>,
+    #[serde(default)]
+    post_data: Option<PostData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostData {
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    params: Vec<Param>,
+}
+
+#[derive(Deserialize)]
+struct Param {
+    name: String,
+    #[serde(default)]
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    content: Content,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    #[serde(default)]
+    mime_type: String,
+}
+
+pub(crate) fn run(args: HarImport) -> Result<(), String> {
+    let content = std::fs::read_to_string(&args.file)
+        .map_err(|e| format!("Не удалось прочитать {}: {}", args.file, e))?;
+    let har: Har = serde_json::from_str(&content)
+        .map_err(|e| format!("Ошибка разбора HAR {}: {}", args.file, e))?;
+
+    let mut entries = Vec::new();
+    for entry in har.log.entries {
+        let started = DateTime::parse_from_rfc3339(&entry.started_date_time)
+            .map_err(|e| format!("Некорректное время {}: {}", entry.started_date_time, e))?;
+        entries.push((started, entry));
+    }
+    entries.sort_by_key(|(started, _)| *started);
+
+    let total = entries.len();
+    let mut skipped: HashMap<&str, usize> = HashMap::new();
+    let mut urls: Vec<UrlConfig> = Vec::new();
+    // Окончание предыдущего перенесенного запроса: от него считается пауза до следующего
+    let mut previous_end: Option<DateTime<FixedOffset>> = None;
+
+    for (started, entry) in &entries {
+        let Ok(url) = Url::parse(&entry.request.url) else {
+            *skipped.entry("некорректный URL").or_default() += 1;
+            continue;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            *skipped.entry("не HTTP").or_default() += 1;
+            continue;
+        }
+        if !matches_domain(&url, &args.domain) {
+            *skipped.entry("домен").or_default() += 1;
+            continue;
+        }
+        let mime_type = entry.response.as_ref().map_or("", |response| response.content.mime_type.as_str());
+        if !matches_content_type(mime_type, &args.content_type) {
+            *skipped.entry("тип содержимого").or_default() += 1;
+            continue;
+        }
+        let Ok(method) = HttpMethod::from_str(&entry.request.method, true) else {
+            *skipped.entry("метод").or_default() += 1;
+            continue;
+        };
+
+        if let (Some(previous), Some(end)) = (urls.last_mut(), previous_end) {
+            previous.think_time_ms = think_time(end, *started, &args);
+        }
+        previous_end = Some(*started + chrono::Duration::microseconds((entry.time.max(0.0) * 1000.0) as i64));
+
+        let mut headers: HashMap<String, String> = entry.request.headers.iter()
+            .filter(|header| keep_header(&header.name))
+            .map(|header| (header.name.clone(), header.value.clone()))
+            .collect();
+        let body = entry.request.post_data.as_ref().and_then(post_body);
+        // mimeType body - его Content-Type, если браузер не записал заголовок
+        if let Some(post_data) = entry.request.post_data.as_ref().filter(|_| body.is_some())
+            && !post_data.mime_type.is_empty()
+            && !headers.keys().any(|name| name.eq_ignore_ascii_case("content-type"))
+        {
+            headers.insert("Content-Type".to_string(), post_data.mime_type.clone());
+        }
+
+        urls.push(UrlConfig {
+            url: entry.request.url.clone(),
+            method: Some(method),
+            // В HAR записан текст body, который отправил браузер: он уходит как есть
+            body_type: body.is_some().then_some(BodyKind::Text),
+            body,
+            headers: (!headers.is_empty()).then_some(headers),
+            weight: None,
+            timeout: None,
+            tls: None,
+            auth: None,
+            sign: None,
+            graphql: None,
+            stream: None,
+            socket_path: None,
+            think_time_ms: None,
+            group: None,
+        });
+    }
+
+    if urls.is_empty() {
+        return Err(format!("В {} нет запросов, подходящих под фильтры (всего записей: {})", args.file, total));
+    }
+
+    eprintln!("📥 Перенесено запросов: {} из {}", urls.len(), total);
+    for (reason, count) in &skipped {
+        eprintln!("  пропущено ({}): {}", reason, count);
+    }
+
+    // Сессия воспроизводится по порядку одним пользователем: каждый запрос один раз
+    let config = MultiTestConfig {
+        name: Path::new(&args.file).file_stem().map(|stem| stem.to_string_lossy().into_owned()),
+        distribution: Some("sequential".to_string()),
+        users: Some(1),
+        total_requests: Some(urls.len()),
+        rps: None,
+        duration_seconds: None,
+        urls,
+        ..Default::default()
+    };
+    write_config(&config, args.output.as_deref())
+}
+
+fn matches_domain(url: &Url, domains: &[String]) -> bool {
+    let Some(host) = url.host_str() else { return false };
+    domains.is_empty() || domains.iter().any(|domain| {
+        let domain = domain.trim_start_matches('.');
+        host.eq_ignore_ascii_case(domain)
+            || host.len() > domain.len()
+                && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+                && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+    })
+}
+
+fn matches_content_type(mime_type: &str, filters: &[String]) -> bool {
+    let mime_type = mime_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    filters.is_empty() || filters.iter().any(|filter| mime_type.starts_with(&filter.to_lowercase()))
+}
+
+// Пауза между окончанием предыдущего запроса и началом следующего;
+// запросы, которые браузер отправлял параллельно, идут без паузы
+fn think_time(previous_end: DateTime<FixedOffset>, started: DateTime<FixedOffset>, args: &HarImport) -> Option<u64> {
+    if args.no_think_time {
+        return None;
+    }
+    let gap = (started - previous_end).num_milliseconds();
+    let gap = args.max_think_time.map_or(gap, |max| gap.min(max as i64));
+    (gap > 0).then_some(gap as u64)
+}
+
+// Body запроса: текст как есть, для форм без текста - собранный из параметров
+fn post_body(post_data: &PostData) -> Option<String> {
+    if let Some(text) = post_data.text.as_ref().filter(|text| !text.is_empty()) {
+        return Some(text.clone());
+    }
+    if post_data.params.is_empty() || !post_data.mime_type.starts_with("application/x-www-form-urlencoded") {
+        return None;
+    }
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(post_data.params.iter().map(|param| (&param.name, &param.value)))
+        .finish();
+    Some(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(max_think_time: Option<u64>, no_think_time: bool) -> HarImport {
+        HarImport {
+            file: "session.har".to_string(),
+            output: None,
+            domain: Vec::new(),
+            content_type: Vec::new(),
+            max_think_time,
+            no_think_time,
+        }
+    }
+
+    fn time(text: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    fn param(name: &str, value: &str) -> Param {
+        Param { name: name.to_string(), value: value.to_string() }
+    }
+
+    #[test]
+    fn domain_filter_matches_subdomains_only() {
+        let domains = vec!["example.com".to_string()];
+        let matches = |url: &str| matches_domain(&Url::parse(url).unwrap(), &domains);
+        assert!(matches("https://example.com/"));
+        assert!(matches("https://api.EXAMPLE.com/v1"));
+        assert!(matches("https://a.b.example.com:8443/"));
+        assert!(!matches("https://evil-example.com/"));
+        assert!(!matches("https://example.com.evil.net/"));
+        assert!(!matches("https://notexample.com/"));
+
+        // Точка в начале фильтра не мешает
+        assert!(matches_domain(&Url::parse("https://api.example.com/").unwrap(), &[".example.com".to_string()]));
+        // Без фильтра подходит любой хост
+        assert!(matches_domain(&Url::parse("https://other.org/").unwrap(), &[]));
+    }
+
+    #[test]
+    fn think_time_is_clamped() {
+        let end = time("2024-10-10T13:55:36.000Z");
+        let started = time("2024-10-10T13:55:38.500Z");
+        assert_eq!(think_time(end, started, &args(None, false)), Some(2500));
+        assert_eq!(think_time(end, started, &args(Some(1000), false)), Some(1000));
+        assert_eq!(think_time(end, started, &args(Some(0), false)), None);
+        // Параллельный запрос начался до окончания предыдущего
+        assert_eq!(think_time(started, end, &args(None, false)), None);
+    }
+
+    #[test]
+    fn no_think_time_drops_pauses() {
+        let end = time("2024-10-10T13:55:36Z");
+        let started = time("2024-10-10T13:56:36Z");
+        assert_eq!(think_time(end, started, &args(None, true)), None);
+    }
+
+    #[test]
+    fn form_params_are_urlencoded() {
+        let post_data = PostData {
+            mime_type: "application/x-www-form-urlencoded; charset=UTF-8".to_string(),
+            text: None,
+            params: vec![param("q", "a b&c=d"), param("имя", "значение"), param("empty", "")],
+        };
+        let body = post_body(&post_data).unwrap();
+        assert_eq!(body, "q=a+b%26c%3Dd&%D0%B8%D0%BC%D1%8F=%D0%B7%D0%BD%D0%B0%D1%87%D0%B5%D0%BD%D0%B8%D0%B5&empty=");
+
+        // Текст, записанный браузером, важнее параметров
+        let post_data = PostData { text: Some("raw=1".to_string()), ..post_data };
+        assert_eq!(post_body(&post_data).as_deref(), Some("raw=1"));
+
+        // Параметры multipart без текста не собрать
+        let post_data = PostData {
+            mime_type: "multipart/form-data".to_string(),
+            text: None,
+            params: vec![param("file", "x")],
+        };
+        assert!(post_body(&post_data).is_none());
+    }
+}
@@ -0,0 +1,68 @@
+mod curl;
+mod har;
+mod openapi;
+mod postman;
+
+pub(crate) use curl::CurlRequest;
+
+use clap::Subcommand;
+use load_test::MultiTestConfig;
+
+#[derive(Subcommand)]
+pub(crate) enum ImportSource {
+    /// Сессия браузера в формате HAR
+    Har(har::HarImport),
+    /// Команды curl из файла, например скопированные из DevTools
+    Curl(curl::CurlImport),
+    /// Спецификация OpenAPI 3 или Swagger 2: по URL на каждую операцию
+    Openapi(openapi::OpenapiImport),
+    /// Коллекция Postman v2.1 с окружением: папки становятся группами отчета
+    Postman(postman::PostmanImport),
+}
+
+pub(crate) fn run(source: ImportSource) -> Result<(), String> {
+    match source {
+        ImportSource::Har(args) => har::run(args),
+        ImportSource::Curl(args) => curl::run(args),
+        ImportSource::Openapi(args) => openapi::run(args),
+        ImportSource::Postman(args) => postman::run(args),
+    }
+}
+
+// Заголовки, которые не переносятся в конфигурацию: hop-by-hop, cookies
+// и те, что клиент выставляет сам
+const SKIPPED_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "cookie",
+    "host",
+    "content-length",
+];
+
+pub(crate) fn keep_header(name: &str) -> bool {
+    // Псевдозаголовки HTTP/2 (:authority, :path) в HAR записываются вместе с обычными
+    !name.starts_with(':') && !SKIPPED_HEADERS.contains(&name.to_lowercase().as_str())
+}
+
+// Сохраняет конфигурацию в файл; без файла выводит YAML в stdout
+fn write_config(config: &MultiTestConfig, output: Option<&str>) -> Result<(), String> {
+    match output {
+        Some(path) => {
+            config.save(path)?;
+            eprintln!("💾 Конфигурация сохранена в {}", path);
+            Ok(())
+        }
+        None => {
+            let yaml = serde_yaml::to_string(config).map_err(|e| e.to_string())?;
+            print!("{}", yaml);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use clap::{Args, ValueEnum};
+use load_test::{BodyKind, HttpMethod, MultiTestConfig, UrlConfig};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::{Map, Value};
+
+use super::write_config;
+
+const METHODS: &[&str] = &["get", "post", "put", "patch", "delete", "head", "options"];
+// Сколько вложенных $ref раскрывать при генерации примера
+const MAX_SCHEMA_DEPTH: usize = 8;
+// Символы, которые остаются как есть в сегменте пути
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+#[derive(Args)]
+pub(crate) struct OpenapiImport {
+    /// Спецификация OpenAPI 3 или Swagger 2 (YAML или JSON)
+    pub spec: String,
+
+    /// Куда сохранить конфигурацию (.json, .yaml, .toml); без флага - YAML в stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Адрес сервиса вместо servers из спецификации; относительный путь из servers добавляется к нему
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Значения параметров: YAML или JSON вида "имя: значение"; без значения параметр генерируется по схеме
+    #[arg(long)]
+    pub data: Option<String>,
+
+    /// Только операции с этими тегами (можно указать несколько)
+    #[arg(long, value_delimiter = ',')]
+    pub tag: Vec<String>,
+}
+
+pub(crate) fn run(args: OpenapiImport) -> Result<(), String> {
+    let spec = load_document(&args.spec)?;
+    let data: HashMap<String, Value> = match &args.data {
+        Some(path) => serde_json::from_value(load_document(path)?)
+            .map_err(|e| format!("Файл {} должен содержать пары имя: значение: {}", path, e))?,
+        None => HashMap::new(),
+    };
+    let base_url = base_url(&spec, args.base_url.as_deref())?;
+    let paths = spec.get("paths").and_then(Value::as_object)
+        .ok_or("В спецификации нет раздела paths")?;
+
+    let mut urls = Vec::new();
+    let mut generated = 0;
+    let mut skipped_by_tag = 0;
+    let mut warnings = Vec::new();
+
+    for (path, item) in paths {
+        let item = resolve(&spec, item);
+        for &method_name in METHODS {
+            let Some(operation) = item.get(method_name) else { continue };
+            let tags: Vec<&str> = operation.get("tags").and_then(Value::as_array)
+                .map(|tags| tags.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            if !args.tag.is_empty() && !tags.iter().any(|tag| args.tag.iter().any(|wanted| wanted == tag)) {
+                skipped_by_tag += 1;
+                continue;
+            }
+            let title = format!("{} {}", method_name.to_uppercase(), path);
+
+            let mut request_path = path.clone();
+            let mut query = url::form_urlencoded::Serializer::new(String::new());
+            let mut headers = HashMap::new();
+            let mut form = Map::new();
+            let mut body_schema = None;
+
+            for parameter in parameters(&spec, item, operation) {
+                let Some(name) = parameter.get("name").and_then(Value::as_str) else { continue };
+                let location = parameter.get("in").and_then(Value::as_str).unwrap_or("");
+                let required = parameter.get("required").and_then(Value::as_bool).unwrap_or(false);
+                if location == "body" {
+                    body_schema = parameter.get("schema").cloned();
+                    continue;
+                }
+                if !required && location != "path" {
+                    continue;
+                }
+
+                let value = match data.get(name) {
+                    Some(Value::Array(values)) => values.first().cloned().unwrap_or(Value::Null),
+                    Some(value) => value.clone(),
+                    None => {
+                        generated += 1;
+                        parameter_example(&spec, parameter)
+                    }
+                };
+                match location {
+                    "path" => {
+                        let value = utf8_percent_encode(&scalar(&value), PATH_SEGMENT).to_string();
+                        request_path = request_path.replace(&format!("{{{}}}", name), &value);
+                    }
+                    "query" => { query.append_pair(name, &scalar(&value)); }
+                    "header" => { headers.insert(name.to_string(), scalar(&value)); }
+                    "formData" => { form.insert(name.to_string(), value); }
+                    _ => {}
+                }
+            }
+
+            let body = if !form.is_empty() {
+                headers.insert("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string());
+                Some(form_body(&form))
+            } else if let Some(schema) = body_schema {
+                headers.insert("Content-Type".to_string(), "application/json".to_string());
+                Some(example_value(&spec, &schema, &mut Vec::new()).to_string())
+            } else {
+                match request_body(&spec, operation) {
+                    Ok(Some((content_type, body))) => {
+                        headers.insert("Content-Type".to_string(), content_type);
+                        Some(body)
+                    }
+                    Ok(None) => None,
+                    Err(content_types) => {
+                        warnings.push(format!("{}: body {} не поддерживается, запрос без body", title, content_types));
+                        None
+                    }
+                }
+            };
+
+            let query = query.finish();
+            let mut url = format!("{}{}", base_url, request_path);
+            if !query.is_empty() {
+                url = format!("{}?{}", url, query);
+            }
+            let Ok(method) = HttpMethod::from_str(method_name, true) else { continue };
+
+            urls.push(UrlConfig {
+                url,
+                method: Some(method),
+                // Body уже в том виде, в каком уходит на сервер: форма закодирована, текст не base64
+                body_type: body.is_some().then_some(BodyKind::Text),
+                body,
+                headers: (!headers.is_empty()).then_some(headers),
+                weight: None,
+                timeout: None,
+                tls: None,
+                auth: None,
+                sign: None,
+                graphql: None,
+                stream: None,
+                socket_path: None,
+                think_time_ms: None,
+                group: tags.first().map(|tag| tag.to_string()),
+            });
+        }
+    }
+
+    if urls.is_empty() {
+        return Err(format!("В {} нет подходящих операций", args.spec));
+    }
+    eprintln!("📥 Перенесено операций: {}", urls.len());
+    if skipped_by_tag > 0 {
+        eprintln!("  пропущено по тегам: {}", skipped_by_tag);
+    }
+    if generated > 0 {
+        eprintln!("  параметров сгенерировано по схеме: {} (задайте их в --data)", generated);
+    }
+    for warning in &warnings {
+        eprintln!("⚠️  {}", warning);
+    }
+
+    let config = MultiTestConfig {
+        name: spec.pointer("/info/title").and_then(Value::as_str).map(str::to_string),
+        urls,
+        rps: None,
+        duration_seconds: None,
+        ..Default::default()
+    };
+    write_config(&config, args.output.as_deref())
+}
+
+// YAML или JSON по расширению файла
+fn load_document(path: &str) -> Result<Value, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?;
+    if path.to_lowercase().ends_with(".json") {
+        serde_json::from_str(&content).map_err(|e| format!("Ошибка разбора {}: {}", path, e))
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| format!("Ошибка разбора {}: {}", path, e))
+    }
+}
+
+// Адрес сервиса: servers[0] в OpenAPI 3, schemes + host + basePath в Swagger 2
+fn base_url(spec: &Value, base_url: Option<&str>) -> Result<String, String> {
+    let server_url = if let Some(server) = spec.pointer("/servers/0") {
+        let mut url = server.get("url").and_then(Value::as_str).unwrap_or("").to_string();
+        if let Some(variables) = server.get("variables").and_then(Value::as_object) {
+            for (name, variable) in variables {
+                if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                    url = url.replace(&format!("{{{}}}", name), default);
+                }
+            }
+        }
+        url
+    } else if let Some(host) = spec.get("host").and_then(Value::as_str) {
+        let scheme = spec.pointer("/schemes/0").and_then(Value::as_str).unwrap_or("https");
+        let base_path = spec.get("basePath").and_then(Value::as_str).unwrap_or("");
+        format!("{}://{}{}", scheme, host, base_path)
+    } else {
+        spec.get("basePath").and_then(Value::as_str).unwrap_or("").to_string()
+    };
+
+    let url = match base_url {
+        Some(base_url) if server_url.contains("://") => base_url.to_string(),
+        Some(base_url) => format!("{}{}", base_url.trim_end_matches('/'), server_url),
+        None if server_url.contains("://") => server_url,
+        None => return Err("В спецификации нет абсолютного адреса сервера: укажите --base-url".to_string()),
+    };
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+// Параметры пути и операции; параметр операции заменяет одноименный параметр пути
+fn parameters<'a>(spec: &'a Value, item: &'a Value, operation: &'a Value) -> Vec<&'a Value> {
+    let mut result: Vec<&Value> = Vec::new();
+    for source in [item, operation] {
+        let Some(list) = source.get("parameters").and_then(Value::as_array) else { continue };
+        for parameter in list {
+            let parameter = resolve(spec, parameter);
+            let key = |parameter: &Value| (parameter.get("name").cloned(), parameter.get("in").cloned());
+            result.retain(|existing| key(existing) != key(parameter));
+            result.push(parameter);
+        }
+    }
+    result
+}
+
+fn parameter_example(spec: &Value, parameter: &Value) -> Value {
+    if let Some(example) = parameter.get("example") {
+        return example.clone();
+    }
+    if let Some(example) = parameter.get("examples").and_then(Value::as_object)
+        .and_then(|examples| examples.values().next())
+        .and_then(|example| resolve(spec, example).get("value"))
+    {
+        return example.clone();
+    }
+    // В Swagger 2 схема записана прямо в параметре
+    let schema = parameter.get("schema").unwrap_or(parameter);
+    example_value(spec, schema, &mut Vec::new())
+}
+
+// Body из requestBody (OpenAPI 3): JSON, форма или текст.
+// Err - в операции есть body только неподдерживаемых типов.
+fn request_body(spec: &Value, operation: &Value) -> Result<Option<(String, String)>, String> {
+    let Some(request_body) = operation.get("requestBody") else { return Ok(None) };
+    let Some(content) = resolve(spec, request_body).get("content").and_then(Value::as_object) else {
+        return Ok(None);
+    };
+
+    let find = |predicate: &dyn Fn(&str) -> bool| content.iter().find(|(media_type, _)| predicate(media_type));
+    let chosen = find(&|media_type| media_type == "application/json" || media_type.ends_with("+json"))
+        .or_else(|| find(&|media_type| media_type == "application/x-www-form-urlencoded"))
+        .or_else(|| find(&|media_type| media_type.starts_with("text/")));
+    let Some((media_type, media)) = chosen else {
+        return Err(content.keys().cloned().collect::<Vec<_>>().join(", "));
+    };
+
+    let value = media.get("example").cloned()
+        .or_else(|| media.get("examples").and_then(Value::as_object)
+            .and_then(|examples| examples.values().next())
+            .and_then(|example| resolve(spec, example).get("value").cloned()))
+        .unwrap_or_else(|| media.get("schema").map_or(Value::Null, |schema| example_value(spec, schema, &mut Vec::new())));
+
+    let body = if media_type == "application/x-www-form-urlencoded" {
+        value.as_object().map(form_body).unwrap_or_default()
+    } else if media_type.starts_with("text/") {
+        scalar(&value)
+    } else {
+        value.to_string()
+    };
+    Ok(Some((media_type.clone(), body)))
+}
+
+// Пример значения по схеме: example, default и enum из схемы, иначе значение по типу и формату.
+// refs - раскрываемые сейчас $ref: рекурсивная схема обрывается пустым значением.
+fn example_value<'a>(spec: &'a Value, schema: &'a Value, refs: &mut Vec<&'a str>) -> Value {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let target = resolve(spec, schema);
+        if refs.contains(&reference) || refs.len() >= MAX_SCHEMA_DEPTH || std::ptr::eq(target, schema) {
+            return Value::Null;
+        }
+        refs.push(reference);
+        let value = example_value(spec, target, refs);
+        refs.pop();
+        return value;
+    }
+    for key in ["example", "default"] {
+        if let Some(value) = schema.get(key) {
+            return value.clone();
+        }
+    }
+    if let Some(value) = schema.get("examples").and_then(Value::as_array).and_then(|values| values.first())
+        .or_else(|| schema.get("enum").and_then(Value::as_array).and_then(|values| values.first()))
+    {
+        return value.clone();
+    }
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in parts {
+            if let Value::Object(object) = example_value(spec, part, refs) {
+                merged.extend(object);
+            }
+        }
+        return Value::Object(merged);
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(first) = schema.get(key).and_then(Value::as_array).and_then(|variants| variants.first()) {
+            return example_value(spec, first, refs);
+        }
+    }
+
+    // В OpenAPI 3.1 type может быть списком: ["string", "null"]
+    let schema_type = match schema.get("type") {
+        Some(Value::String(schema_type)) => schema_type.as_str(),
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).find(|t| *t != "null").unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "",
+    };
+    match schema_type {
+        "object" => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let object = properties.into_iter().flatten()
+                .map(|(name, property)| (name.clone(), example_value(spec, property, refs)))
+                .filter(|(_, value)| !value.is_null())
+                .collect();
+            Value::Object(object)
+        }
+        "array" => {
+            let item = schema.get("items").map_or(Value::Null, |items| example_value(spec, items, refs));
+            Value::Array(if item.is_null() { Vec::new() } else { vec![item] })
+        }
+        "integer" => schema.get("minimum").cloned().unwrap_or(Value::from(1)),
+        "number" => schema.get("minimum").cloned().unwrap_or(Value::from(1.0)),
+        "boolean" => Value::Bool(true),
+        "string" => Value::String(string_example(schema)),
+        _ => Value::Null,
+    }
+}
+
+fn string_example(schema: &Value) -> String {
+    let mut value = match schema.get("format").and_then(Value::as_str).unwrap_or("") {
+        "date-time" => "2024-01-01T00:00:00Z",
+        "date" => "2024-01-01",
+        "time" => "12:00:00",
+        "email" => "user@example.com",
+        "uuid" => "00000000-0000-4000-8000-000000000000",
+        "uri" | "url" => "https://example.com",
+        "hostname" => "example.com",
+        "ipv4" => "192.0.2.1",
+        "ipv6" => "2001:db8::1",
+        "byte" => "dGVzdA==",
+        "password" => "password",
+        _ => "test",
+    }
+    .to_string();
+    let min_length = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0) as usize;
+    if value.len() < min_length {
+        value.push_str(&"x".repeat(min_length - value.len()));
+    }
+    value
+}
+
+// Переходит по локальным ссылкам $ref (#/components/..., #/definitions/...)
+fn resolve<'a>(spec: &'a Value, mut value: &'a Value) -> &'a Value {
+    for _ in 0..16 {
+        let Some(reference) = value.get("$ref").and_then(Value::as_str) else { break };
+        let Some(pointer) = reference.strip_prefix('#') else { break };
+        match spec.pointer(pointer) {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+    value
+}
+
+// Значение параметра как строка: строки без кавычек JSON
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn form_body(fields: &Map<String, Value>) -> String {
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(fields.iter().map(|(name, value)| (name, scalar(value))))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn example(spec: &Value, schema: Value) -> Value {
+        example_value(spec, &schema, &mut Vec::new())
+    }
+
+    #[test]
+    fn recursive_ref_is_cut_off() {
+        let spec = json!({"components": {"schemas": {
+            "Node": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "parent": {"$ref": "#/components/schemas/Node"},
+                    "children": {"type": "array", "items": {"$ref": "#/components/schemas/Node"}},
+                },
+            },
+            "Loop": {"$ref": "#/components/schemas/Loop"},
+        }}});
+        assert_eq!(
+            example(&spec, json!({"$ref": "#/components/schemas/Node"})),
+            json!({"name": "test", "children": []})
+        );
+        assert_eq!(example(&spec, json!({"$ref": "#/components/schemas/Loop"})), Value::Null);
+        assert_eq!(example(&spec, json!({"$ref": "#/components/schemas/Missing"})), Value::Null);
+    }
+
+    #[test]
+    fn all_of_parts_are_merged() {
+        let spec = json!({"definitions": {
+            "Base": {"properties": {"id": {"type": "integer", "minimum": 10}, "kind": {"enum": ["a", "b"]}}},
+        }});
+        let schema = json!({"allOf": [
+            {"$ref": "#/definitions/Base"},
+            {"type": "object", "properties": {"email": {"type": "string", "format": "email"}, "kind": {"example": "b"}}},
+        ]});
+        assert_eq!(example(&spec, schema), json!({"id": 10, "kind": "b", "email": "user@example.com"}));
+    }
+
+    #[test]
+    fn swagger2_host_and_base_path() {
+        let spec = json!({"swagger": "2.0", "host": "api.example.com:8080", "basePath": "/v1/", "schemes": ["http", "https"]});
+        assert_eq!(base_url(&spec, None).unwrap(), "http://api.example.com:8080/v1");
+
+        let spec = json!({"swagger": "2.0", "host": "api.example.com"});
+        assert_eq!(base_url(&spec, None).unwrap(), "https://api.example.com");
+
+        // Без host адрес берется из --base-url, basePath добавляется к нему
+        let spec = json!({"swagger": "2.0", "basePath": "/v1"});
+        assert_eq!(base_url(&spec, Some("http://localhost:3000/")).unwrap(), "http://localhost:3000/v1");
+    }
+
+    #[test]
+    fn relative_server_url_needs_base_url() {
+        let spec = json!({"openapi": "3.0.0", "servers": [{"url": "/api/v2"}]});
+        assert!(base_url(&spec, None).unwrap_err().contains("--base-url"));
+        assert_eq!(base_url(&spec, Some("https://staging.local/")).unwrap(), "https://staging.local/api/v2");
+
+        let spec = json!({"servers": [{
+            "url": "https://{region}.example.com/{version}/",
+            "variables": {"region": {"default": "eu"}, "version": {"default": "v3"}},
+        }]});
+        assert_eq!(base_url(&spec, None).unwrap(), "https://eu.example.com/v3");
+        // Абсолютный адрес из servers заменяется целиком
+        assert_eq!(base_url(&spec, Some("http://127.0.0.1:8080")).unwrap(), "http://127.0.0.1:8080");
+    }
+
+    #[test]
+    fn operation_parameters_override_path_parameters() {
+        let spec = json!({"components": {"parameters": {
+            "Limit": {"name": "limit", "in": "query", "required": true, "schema": {"type": "integer"}},
+        }}});
+        let item = json!({"parameters": [
+            {"name": "id", "in": "path", "schema": {"type": "string"}},
+            {"name": "id", "in": "header", "schema": {"type": "string"}},
+            {"name": "limit", "in": "query", "schema": {"type": "integer"}},
+        ]});
+        let operation = json!({"parameters": [
+            {"name": "id", "in": "path", "example": 42},
+            {"$ref": "#/components/parameters/Limit"},
+        ]});
+
+        let parameters = parameters(&spec, &item, &operation);
+        let summary: Vec<_> = parameters.iter()
+            .map(|parameter| (parameter["name"].as_str().unwrap(), parameter["in"].as_str().unwrap()))
+            .collect();
+        assert_eq!(summary, [("id", "header"), ("id", "path"), ("limit", "query")]);
+        assert_eq!(parameter_example(&spec, parameters[1]), json!(42));
+        assert_eq!(parameters[2]["required"], json!(true));
+    }
+}
@@ -0,0 +1,776 @@
+use std::collections::{BTreeSet, HashMap};
+
+use clap::{Args, ValueEnum};
+use base64::{engine::general_purpose, Engine as _};
+use load_test::{AuthConfig, BodyKind, HttpMethod, MultiTestConfig, SigningConfig, UrlConfig};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::curl::{multipart_body, FormPart, FORM_BOUNDARY};
+use super::{keep_header, write_config};
+
+// Сколько раз раскрывать переменные, которые ссылаются на другие переменные
+const MAX_VARIABLE_DEPTH: usize = 8;
+
+#[derive(Args)]
+pub(crate) struct PostmanImport {
+    /// Коллекция Postman v2.1 (JSON)
+    pub collection: String,
+
+    /// Куда сохранить конфигурацию (.json, .yaml, .toml); без флага - YAML в stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Окружение Postman (JSON): его переменные подставляются вместо {{имя}}
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    /// Значение переменной "имя=значение", важнее окружения и коллекции (можно указать несколько)
+    #[arg(long = "var")]
+    pub vars: Vec<String>,
+
+    /// Только запросы из этих папок и их подпапок (можно указать несколько)
+    #[arg(long, value_delimiter = ',')]
+    pub folder: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Collection {
+    info: Info,
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default)]
+    auth: Option<Auth>,
+    #[serde(default)]
+    variable: Vec<Variable>,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    name: String,
+    #[serde(default)]
+    schema: String,
+}
+
+// Папка (есть item) или запрос (есть request)
+#[derive(Deserialize)]
+struct Item {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    item: Option<Vec<Item>>,
+    #[serde(default)]
+    request: Option<RequestDef>,
+    #[serde(default)]
+    auth: Option<Auth>,
+}
+
+// Запрос может быть записан одной строкой URL
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RequestDef {
+    Url(String),
+    Full(Box<Request>),
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    url: Option<UrlDef>,
+    #[serde(default)]
+    header: Vec<KeyValue>,
+    #[serde(default)]
+    body: Option<Body>,
+    #[serde(default)]
+    auth: Option<Auth>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UrlDef {
+    Raw(String),
+    Parts(UrlParts),
+}
+
+#[derive(Deserialize)]
+struct UrlParts {
+    #[serde(default)]
+    raw: Option<String>,
+    #[serde(default)]
+    protocol: Option<String>,
+    // Строка или массив частей
+    #[serde(default)]
+    host: Option<Value>,
+    #[serde(default)]
+    port: Option<String>,
+    #[serde(default)]
+    path: Option<Value>,
+    #[serde(default)]
+    query: Vec<KeyValue>,
+    // Значения параметров пути :id
+    #[serde(default)]
+    variable: Vec<Variable>,
+}
+
+#[derive(Deserialize)]
+struct KeyValue {
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Body {
+    #[serde(default)]
+    mode: String,
+    #[serde(default)]
+    raw: Option<String>,
+    #[serde(default)]
+    urlencoded: Vec<KeyValue>,
+    #[serde(default)]
+    formdata: Vec<FormParam>,
+    #[serde(default)]
+    graphql: Option<GraphqlBody>,
+    #[serde(default)]
+    file: Option<FileBody>,
+    #[serde(default)]
+    options: Option<Value>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FormParam {
+    key: String,
+    #[serde(default)]
+    value: Option<String>,
+    // text или file
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    // Путь к файлу или список путей
+    #[serde(default)]
+    src: Option<Value>,
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Deserialize)]
+struct GraphqlBody {
+    #[serde(default)]
+    query: String,
+    #[serde(default)]
+    variables: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FileBody {
+    #[serde(default)]
+    src: Option<String>,
+}
+
+// Параметры авторизации лежат в поле с именем типа: {"type": "bearer", "bearer": [{"key": "token", ...}]}
+#[derive(Deserialize)]
+struct Auth {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(flatten)]
+    params: HashMap<String, Value>,
+}
+
+impl Auth {
+    fn param(&self, key: &str) -> Option<String> {
+        let value = match self.params.get(&self.kind)? {
+            Value::Array(params) => params.iter()
+                .find(|param| param.get("key").and_then(Value::as_str) == Some(key))?
+                .get("value")?,
+            // Формат v2.0: параметры объектом
+            Value::Object(params) => params.get(key)?,
+            _ => return None,
+        };
+        match value {
+            Value::String(value) => Some(value.clone()),
+            Value::Null => None,
+            value => Some(value.to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Variable {
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default)]
+    value: Value,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Deserialize)]
+struct Environment {
+    #[serde(default)]
+    values: Vec<EnvironmentValue>,
+}
+
+#[derive(Deserialize)]
+struct EnvironmentValue {
+    key: String,
+    #[serde(default)]
+    value: Value,
+    #[serde(default = "enabled")]
+    enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+// Переменные коллекции, окружения и --var; собирает имена, которые не удалось подставить
+#[derive(Default)]
+struct Variables {
+    values: HashMap<String, String>,
+    unresolved: BTreeSet<String>,
+    // В body подставлены {{uuid}} или {{timestamp}}
+    dynamic: bool,
+}
+
+impl Variables {
+    fn insert(&mut self, key: String, value: &Value) {
+        let value = match value {
+            Value::String(value) => value.clone(),
+            Value::Null => String::new(),
+            value => value.to_string(),
+        };
+        self.values.insert(key, value);
+    }
+
+    fn resolve(&mut self, text: &str) -> String {
+        self.substitute(text, false, 0)
+    }
+
+    // В body динамические переменные Postman заменяются на подстановки multi
+    fn resolve_body(&mut self, text: &str) -> String {
+        self.substitute(text, true, 0)
+    }
+
+    fn substitute(&mut self, text: &str, body: bool, depth: usize) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start + 2..].find("}}") else { break };
+            let name = rest[start + 2..start + 2 + end].trim();
+            result.push_str(&rest[..start]);
+            let placeholder = &rest[start..start + end + 4];
+
+            match (name, self.values.get(name)) {
+                ("$guid" | "$randomUUID", _) if body => {
+                    self.dynamic = true;
+                    result.push_str("{{uuid}}");
+                }
+                ("$timestamp" | "$isoTimestamp", _) if body => {
+                    self.dynamic = true;
+                    result.push_str("{{timestamp}}");
+                }
+                (_, Some(value)) if depth < MAX_VARIABLE_DEPTH => {
+                    let value = value.clone();
+                    result.push_str(&self.substitute(&value, body, depth + 1));
+                }
+                _ => {
+                    self.unresolved.insert(name.to_string());
+                    result.push_str(placeholder);
+                }
+            }
+            rest = &rest[start + end + 4..];
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+// Что получилось из блока auth
+#[derive(Default)]
+struct AuthParts {
+    auth: Option<AuthConfig>,
+    sign: Option<SigningConfig>,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+}
+
+struct Importer<'a> {
+    variables: Variables,
+    folders: &'a [String],
+    urls: Vec<UrlConfig>,
+    warnings: Vec<String>,
+}
+
+pub(crate) fn run(args: PostmanImport) -> Result<(), String> {
+    let content = std::fs::read_to_string(&args.collection)
+        .map_err(|e| format!("Не удалось прочитать {}: {}", args.collection, e))?;
+    let collection: Collection = serde_json::from_str(&content)
+        .map_err(|e| format!("Ошибка разбора коллекции {}: {}", args.collection, e))?;
+    if !collection.info.schema.is_empty() && !collection.info.schema.contains("v2.1") {
+        eprintln!("⚠️  Коллекция в формате {}, поддерживается v2.1", collection.info.schema);
+    }
+
+    // Окружение важнее переменных коллекции, --var важнее окружения
+    let mut variables = Variables::default();
+    for variable in collection.variable.iter().filter(|variable| !variable.disabled) {
+        if let Some(key) = &variable.key {
+            variables.insert(key.clone(), &variable.value);
+        }
+    }
+    if let Some(path) = &args.environment {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Не удалось прочитать {}: {}", path, e))?;
+        let environment: Environment = serde_json::from_str(&content)
+            .map_err(|e| format!("Ошибка разбора окружения {}: {}", path, e))?;
+        for value in environment.values.into_iter().filter(|value| value.enabled) {
+            variables.insert(value.key, &value.value);
+        }
+    }
+    for var in &args.vars {
+        let (key, value) = var.split_once('=')
+            .ok_or_else(|| format!("Ожидается --var имя=значение: {}", var))?;
+        variables.insert(key.to_string(), &Value::String(value.to_string()));
+    }
+
+    let mut importer = Importer {
+        variables,
+        folders: &args.folder,
+        urls: Vec::new(),
+        warnings: Vec::new(),
+    };
+    importer.items(&collection.item, &[], collection.auth.as_ref(), args.folder.is_empty());
+
+    // Предупреждения объясняют пропущенные запросы, поэтому выводятся и когда переносить нечего
+    for warning in &importer.warnings {
+        eprintln!("⚠️  {}", warning);
+    }
+    if importer.urls.is_empty() {
+        return Err(format!("В {} нет запросов, подходящих под фильтры", args.collection));
+    }
+
+    eprintln!("📥 Перенесено запросов: {}", importer.urls.len());
+    if !importer.variables.unresolved.is_empty() {
+        let names: Vec<&str> = importer.variables.unresolved.iter().map(String::as_str).collect();
+        eprintln!("⚠️  Не заданы переменные (остались как есть): {}", names.join(", "));
+    }
+
+    let config = MultiTestConfig {
+        name: Some(collection.info.name),
+        urls: importer.urls,
+        rps: None,
+        duration_seconds: None,
+        dynamic_body: importer.variables.dynamic.then_some(true),
+        ..Default::default()
+    };
+    write_config(&config, args.output.as_deref())
+}
+
+impl Importer<'_> {
+    // Обходит папки; группа запроса - путь папок через " / ", авторизация наследуется от ближайшей папки
+    fn items(&mut self, items: &[Item], path: &[&str], parent_auth: Option<&Auth>, selected: bool) {
+        for item in items {
+            let auth = match &item.auth {
+                Some(auth) if auth.kind != "inherit" => Some(auth),
+                _ => parent_auth,
+            };
+            if let Some(children) = &item.item {
+                let mut folder = path.to_vec();
+                folder.push(&item.name);
+                let selected = selected || self.folders.iter().any(|name| name == &item.name);
+                self.items(children, &folder, auth, selected);
+            } else if let (Some(request), true) = (&item.request, selected) {
+                let group = (!path.is_empty()).then(|| path.join(" / "));
+                let title = match &group {
+                    Some(group) => format!("{} / {}", group, item.name),
+                    None => item.name.clone(),
+                };
+                match self.request(request, auth, &title) {
+                    Ok(url) => self.urls.push(UrlConfig { group, ..url }),
+                    Err(e) => self.warnings.push(format!("{}: {}, запрос пропущен", title, e)),
+                }
+            }
+        }
+    }
+
+    fn request(&mut self, request: &RequestDef, parent_auth: Option<&Auth>, title: &str) -> Result<UrlConfig, String> {
+        let (method, url, header, body, own_auth) = match request {
+            RequestDef::Url(url) => ("GET", self.variables.resolve(url), &[][..], None, None),
+            RequestDef::Full(request) => {
+                let url = request.url.as_ref().ok_or("нет URL")?;
+                let method = request.method.as_deref().unwrap_or("GET");
+                (method, self.url(url), &request.header[..], request.body.as_ref(), request.auth.as_ref())
+            }
+        };
+        let method = HttpMethod::from_str(method, true)
+            .map_err(|_| format!("метод {} не поддерживается", method))?;
+        let auth = match own_auth {
+            Some(auth) if auth.kind != "inherit" => Some(auth),
+            _ => parent_auth,
+        };
+        let auth = match auth {
+            Some(auth) => self.auth(auth, title),
+            None => AuthParts::default(),
+        };
+
+        let mut headers = Vec::new();
+        for header in header.iter().filter(|header| !header.disabled) {
+            let Some(name) = header.key.as_deref().filter(|name| keep_header(name)) else { continue };
+            let value = self.variables.resolve(header.value.as_deref().unwrap_or_default());
+            headers.push((name.to_string(), value));
+        }
+        let (body, body_type) = match body.filter(|body| !body.disabled) {
+            Some(body) => self.body(body, &mut headers, title)?.unzip(),
+            None => (None, None),
+        };
+        headers.extend(auth.headers);
+
+        let mut url = url;
+        if !url.contains("://") {
+            url = format!("http://{}", url);
+        }
+        if !auth.query.is_empty() {
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&auth.query)
+                .finish();
+            url = format!("{}{}{}", url, if url.contains('?') { '&' } else { '?' }, query);
+        }
+
+        Ok(UrlConfig {
+            url,
+            method: Some(method),
+            body,
+            body_type,
+            headers: (!headers.is_empty()).then(|| headers.into_iter().collect()),
+            weight: None,
+            timeout: None,
+            tls: None,
+            auth: auth.auth,
+            sign: auth.sign,
+            graphql: None,
+            stream: None,
+            socket_path: None,
+            think_time_ms: None,
+            group: None,
+        })
+    }
+
+    // URL из raw, а если его нет - из частей; параметры пути :id заменяются значениями из variable
+    fn url(&mut self, url: &UrlDef) -> String {
+        let parts = match url {
+            UrlDef::Raw(raw) => return self.variables.resolve(raw),
+            UrlDef::Parts(parts) => parts,
+        };
+        let raw = parts.raw.clone().unwrap_or_else(|| url_from_parts(parts));
+        let mut path_variables = HashMap::new();
+        for variable in parts.variable.iter().filter(|variable| !variable.disabled) {
+            if let (Some(key), Value::String(value)) = (&variable.key, &variable.value) {
+                path_variables.insert(key.as_str(), self.variables.resolve(value));
+            }
+        }
+        let raw = match raw.find(['?', '#']) {
+            Some(index) => format!("{}{}", replace_path_variables(&raw[..index], &path_variables), &raw[index..]),
+            None => replace_path_variables(&raw, &path_variables),
+        };
+        self.variables.resolve(&raw)
+    }
+
+    // Body по режиму Postman с типом, с которым его читает multi: текст отправляется как есть,
+    // multipart и двоичные файлы - как base64. Content-Type добавляется, если его нет в заголовках запроса
+    fn body(
+        &mut self,
+        body: &Body,
+        headers: &mut Vec<(String, String)>,
+        title: &str,
+    ) -> Result<Option<(String, BodyKind)>, String> {
+        match body.mode.as_str() {
+            "raw" => {
+                let Some(raw) = body.raw.as_deref().filter(|raw| !raw.is_empty()) else { return Ok(None) };
+                let language = body.options.as_ref()
+                    .and_then(|options| options.pointer("/raw/language"))
+                    .and_then(Value::as_str);
+                let content_type = match language {
+                    Some("json") => Some("application/json"),
+                    Some("xml") => Some("application/xml"),
+                    Some("html") => Some("text/html"),
+                    Some("text") => Some("text/plain"),
+                    Some("javascript") => Some("application/javascript"),
+                    _ => None,
+                };
+                if let Some(content_type) = content_type {
+                    default_content_type(headers, content_type);
+                }
+                Ok(Some((self.variables.resolve_body(raw), BodyKind::Text)))
+            }
+            "urlencoded" => {
+                let mut pairs = Vec::new();
+                for param in body.urlencoded.iter().filter(|param| !param.disabled) {
+                    let Some(key) = &param.key else { continue };
+                    let value = self.variables.resolve_body(param.value.as_deref().unwrap_or_default());
+                    pairs.push(form_pair(key, &value));
+                }
+                if pairs.is_empty() {
+                    return Ok(None);
+                }
+                default_content_type(headers, "application/x-www-form-urlencoded");
+                Ok(Some((pairs.join("&"), BodyKind::Text)))
+            }
+            "formdata" => {
+                let mut parts = Vec::new();
+                for param in body.formdata.iter().filter(|param| !param.disabled) {
+                    let name = param.key.clone();
+                    if param.kind.as_deref() == Some("file") {
+                        let src = match &param.src {
+                            Some(Value::String(src)) => Some(src.as_str()),
+                            Some(Value::Array(sources)) => sources.first().and_then(Value::as_str),
+                            _ => None,
+                        };
+                        let path = src.ok_or_else(|| format!("у поля формы {} не указан файл", param.key))?.to_string();
+                        let content_type = param.content_type.clone()
+                            .unwrap_or_else(|| "application/octet-stream".to_string());
+                        parts.push(FormPart::File { name, path, content_type });
+                    } else {
+                        // Текст отправляется как есть: значение с @ или < - не путь к файлу
+                        let value = self.variables.resolve_body(param.value.as_deref().unwrap_or_default());
+                        parts.push(FormPart::Text { name, value: value.into_bytes() });
+                    }
+                }
+                if parts.is_empty() {
+                    return Ok(None);
+                }
+                // Граница multipart своя, поэтому Content-Type из коллекции заменяется
+                headers.retain(|(name, _)| !name.eq_ignore_ascii_case("content-type"));
+                headers.push(("Content-Type".to_string(), format!("multipart/form-data; boundary={}", FORM_BOUNDARY)));
+                let body = multipart_body(&parts)?;
+                Ok(Some((general_purpose::STANDARD.encode(body), BodyKind::Base64)))
+            }
+            "graphql" => {
+                let Some(graphql) = &body.graphql else { return Ok(None) };
+                let query = self.variables.resolve_body(&graphql.query);
+                let variables = match graphql.variables.as_deref().map(str::trim).filter(|text| !text.is_empty()) {
+                    Some(text) => serde_json::from_str(&self.variables.resolve_body(text))
+                        .map_err(|e| format!("некорректные переменные GraphQL: {}", e))?,
+                    None => Value::Null,
+                };
+                default_content_type(headers, "application/json");
+                Ok(Some((serde_json::json!({ "query": query, "variables": variables }).to_string(), BodyKind::Text)))
+            }
+            "file" => {
+                let Some(src) = body.file.as_ref().and_then(|file| file.src.as_deref()) else { return Ok(None) };
+                let content = std::fs::read(src)
+                    .map_err(|e| format!("не удалось прочитать body из {}: {}", src, e))?;
+                Ok(Some(match String::from_utf8(content) {
+                    Ok(text) => (text, BodyKind::Text),
+                    Err(e) => (general_purpose::STANDARD.encode(e.into_bytes()), BodyKind::Base64),
+                }))
+            }
+            "" | "none" => Ok(None),
+            mode => {
+                self.warnings.push(format!("{}: body в режиме {} не поддерживается, запрос без body", title, mode));
+                Ok(None)
+            }
+        }
+    }
+
+    // Блок auth: basic, OAuth2 client credentials и AWS SigV4 - в поля auth и sign,
+    // токены и API-ключи - в заголовки или query
+    fn auth(&mut self, auth: &Auth, title: &str) -> AuthParts {
+        let mut parts = AuthParts::default();
+        match auth.kind.as_str() {
+            "noauth" => {}
+            "basic" => {
+                parts.auth = Some(AuthConfig::Basic {
+                    username: self.auth_param(auth, "username").unwrap_or_default(),
+                    password: self.auth_param(auth, "password"),
+                });
+            }
+            "bearer" => {
+                let token = self.auth_param(auth, "token").unwrap_or_default();
+                parts.headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+            }
+            "apikey" => {
+                let key = self.auth_param(auth, "key").unwrap_or_else(|| "X-API-Key".to_string());
+                let value = self.auth_param(auth, "value").unwrap_or_default();
+                match self.auth_param(auth, "in").as_deref() {
+                    Some("query") => parts.query.push((key, value)),
+                    _ => parts.headers.push((key, value)),
+                }
+            }
+            "oauth2" => {
+                let grant_type = self.auth_param(auth, "grant_type");
+                let token_url = self.auth_param(auth, "accessTokenUrl");
+                let client_id = self.auth_param(auth, "clientId");
+                let client_secret = self.auth_param(auth, "clientSecret");
+                match (grant_type.as_deref(), token_url, client_id, client_secret) {
+                    (Some("client_credentials"), Some(token_url), Some(client_id), Some(client_secret)) => {
+                        parts.auth = Some(AuthConfig::OAuth2 {
+                            token_url,
+                            client_id,
+                            client_secret,
+                            scope: self.auth_param(auth, "scope"),
+                            audience: self.auth_param(auth, "audience"),
+                        });
+                    }
+                    _ => match self.auth_param(auth, "accessToken") {
+                        Some(token) => {
+                            let prefix = self.auth_param(auth, "headerPrefix").unwrap_or_else(|| "Bearer".to_string());
+                            parts.headers.push(("Authorization".to_string(), format!("{} {}", prefix, token)));
+                        }
+                        None => self.warnings.push(format!(
+                            "{}: OAuth2 поддерживается для client credentials или с готовым accessToken, запрос без авторизации",
+                            title
+                        )),
+                    },
+                }
+            }
+            "awsv4" => {
+                parts.sign = Some(SigningConfig::AwsSigV4 {
+                    region: self.auth_param(auth, "region").unwrap_or_else(|| "us-east-1".to_string()),
+                    service: self.auth_param(auth, "service").unwrap_or_else(|| "execute-api".to_string()),
+                    access_key_id: self.auth_param(auth, "accessKey"),
+                    secret_access_key: self.auth_param(auth, "secretKey"),
+                    session_token: self.auth_param(auth, "sessionToken"),
+                });
+            }
+            kind => self.warnings.push(format!("{}: авторизация {} не поддерживается, запрос без авторизации", title, kind)),
+        }
+        parts
+    }
+
+    // Параметр авторизации с подставленными переменными; пустой - как отсутствующий
+    fn auth_param(&mut self, auth: &Auth, key: &str) -> Option<String> {
+        auth.param(key)
+            .map(|value| self.variables.resolve(&value))
+            .filter(|value| !value.is_empty())
+    }
+}
+
+fn url_from_parts(parts: &UrlParts) -> String {
+    let join = |value: &Option<Value>, separator: &str| match value {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(separator),
+        _ => String::new(),
+    };
+    let mut url = format!("{}://{}", parts.protocol.as_deref().unwrap_or("http"), join(&parts.host, "."));
+    if let Some(port) = &parts.port {
+        url = format!("{}:{}", url, port);
+    }
+    let path = join(&parts.path, "/");
+    if !path.is_empty() {
+        url = format!("{}/{}", url, path.trim_start_matches('/'));
+    }
+    let query: Vec<String> = parts.query.iter()
+        .filter(|param| !param.disabled)
+        .filter_map(|param| Some(format!("{}={}", param.key.as_deref()?, param.value.as_deref().unwrap_or_default())))
+        .collect();
+    if !query.is_empty() {
+        url = format!("{}?{}", url, query.join("&"));
+    }
+    url
+}
+
+// Сегменты пути вида :id заменяются значениями; сегменты без значения остаются как есть
+fn replace_path_variables(path: &str, values: &HashMap<&str, String>) -> String {
+    path.split('/')
+        .map(|segment| {
+            segment.strip_prefix(':')
+                .and_then(|name| values.get(name))
+                .map_or(segment, String::as_str)
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn default_content_type(headers: &mut Vec<(String, String)>, content_type: &str) {
+    if !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("content-type")) {
+        headers.push(("Content-Type".to_string(), content_type.to_string()));
+    }
+}
+
+// Поле формы в кодировке application/x-www-form-urlencoded; подстановки {{uuid}} и {{timestamp}}
+// остаются некодированными, чтобы их заменил dynamic_body
+fn form_pair(key: &str, value: &str) -> String {
+    let mut pair = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair(key, value)
+        .finish();
+    for placeholder in ["{{uuid}}", "{{timestamp}}"] {
+        let encoded: String = url::form_urlencoded::byte_serialize(placeholder.as_bytes()).collect();
+        pair = pair.replace(&encoded, placeholder);
+    }
+    pair
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import_request(request: Value) -> (UrlConfig, bool) {
+        let mut importer = Importer {
+            variables: Variables::default(),
+            folders: &[],
+            urls: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let request: RequestDef = serde_json::from_value(request).unwrap();
+        let url = importer.request(&request, None, "test").unwrap();
+        (url, importer.variables.dynamic)
+    }
+
+    #[test]
+    fn urlencoded_fields_are_encoded_and_keep_placeholders() {
+        let (url, dynamic) = import_request(serde_json::json!({
+            "method": "POST",
+            "url": "https://api.example.com/events",
+            "body": {
+                "mode": "urlencoded",
+                "urlencoded": [
+                    {"key": "name", "value": "a b"},
+                    {"key": "at", "value": "{{$timestamp}}"}
+                ]
+            }
+        }));
+        assert!(dynamic);
+        assert_eq!(url.headers.unwrap()["Content-Type"], "application/x-www-form-urlencoded");
+        assert_eq!(url.body.as_deref(), Some("name=a+b&at={{timestamp}}"));
+        assert_eq!(url.body_type, Some(BodyKind::Text));
+    }
+
+    #[test]
+    fn formdata_text_is_never_read_as_file() {
+        let path = std::env::temp_dir().join(format!("load_test_{}_avatar.txt", std::process::id()));
+        std::fs::write(&path, "file content").unwrap();
+        let (url, dynamic) = import_request(serde_json::json!({
+            "method": "POST",
+            "url": "https://api.example.com/profile",
+            "body": {
+                "mode": "formdata",
+                "formdata": [
+                    {"key": "handle", "value": "@bob", "type": "text"},
+                    {"key": "quote", "value": "<html>"},
+                    {"key": "request", "value": "{{$guid}}", "type": "text"},
+                    {"key": "avatar", "type": "file", "src": path.to_str().unwrap(), "contentType": "text/plain"}
+                ]
+            }
+        }));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(dynamic);
+        assert_eq!(url.body_type, Some(BodyKind::Base64));
+        let body = String::from_utf8(general_purpose::STANDARD.decode(url.body.unwrap()).unwrap()).unwrap();
+        let part = |name: &str, value: &str| format!("name=\"{}\"\r\n\r\n{}\r\n", name, value);
+        assert!(body.contains(&part("handle", "@bob")), "{}", body);
+        assert!(body.contains(&part("quote", "<html>")), "{}", body);
+        assert!(body.contains(&part("request", "{{uuid}}")), "{}", body);
+        assert!(body.contains("filename=\"load_test_"), "{}", body);
+        assert!(body.contains("Content-Type: text/plain\r\n\r\nfile content\r\n"), "{}", body);
+    }
+}
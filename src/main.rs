@@ -1,36 +1,108 @@
 use std::time::{Duration, Instant};
 use std::sync::Arc;
-use std::collections::HashMap;
-use load_test::HttpMethod;
-use reqwest::{Client};
+use std::collections::{HashMap, HashSet};
+use load_test::{
+    mask_secrets, unmasked_secrets, AbortCondition, AuthConfig, BodyKind, ClientOptions, ConfigEnv, GraphqlConfig,
+    GraphqlOptions, HttpMethod, MultiTestConfig, SigningConfig, StreamConfig, StreamOptions, TlsOptions, UrlConfig,
+    MIN_SECRET_LEN,
+};
+use reqwest::{Client, Method, StatusCode};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, COOKIE, LOCATION,
+    PROXY_AUTHORIZATION,
+};
 use serde_json::{Value};
-use tokio::sync::Semaphore;
-use futures::future::join_all;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use bytes::Bytes;
 use chrono::Utc;
 use clap::{Parser, Subcommand, ValueEnum};
 use url::Url;
 use base64::{Engine as _, engine::general_purpose};
 use rand::Rng;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::fs;
+
+mod auth;
+mod client;
+mod connect;
+mod dns;
+mod grpc;
+mod import;
+mod guardrails;
+mod raw;
+mod replay;
+mod shutdown;
+mod signing;
+mod stats;
+mod stream;
+mod unix;
+mod ws;
+
+use auth::{AuthProviders, Authorization};
+use client::ClientSet;
+use guardrails::Guardrails;
+use shutdown::{spawn_signal_listener, stop_channel, StopHandle, StopSignal};
+use signing::Signer;
+use stats::{classify_transport, spawn_aggregator, Aggregator, ErrorClass, RequestResult, TestStats};
+use import::CurlRequest;
+use unix::UnixClient;
 
 // Структура для распределения запросов по URL
 struct MultiUrlTester {
-    configs: Vec<RequestConfig>,
+    configs: Vec<RequestTemplate>,
     distribution: UrlDistribution,
     current_index: AtomicUsize,
 }
 
 impl MultiUrlTester {
-    fn new(configs: Vec<RequestConfig>, distribution: UrlDistribution) -> Self {
-        Self {
+    // Токены авторизации получаются здесь, до начала теста
+    async fn new(
+        configs: Vec<RequestConfig>,
+        distribution: UrlDistribution,
+        client_options: &ClientOptions,
+    ) -> Result<Self, String> {
+        let mut templates = Vec::with_capacity(configs.len());
+        for config in configs {
+            templates.push(RequestTemplate::new(config, client_options).await?);
+        }
+        let mut configs = templates;
+
+        // Один URL с разными методами (GET и DELETE /items/1) считается в отчете раздельно
+        let mut url_methods: HashMap<Arc<str>, HashSet<Method>> = HashMap::new();
+        for template in &configs {
+            url_methods.entry(template.url_str.clone()).or_default().insert(template.method.clone());
+        }
+        for template in &mut configs {
+            if url_methods[&template.url_str].len() > 1 {
+                template.url_str = Arc::from(format!("{} {}", template.method, template.url_str));
+            }
+        }
+
+        // Авторизация URL из файла multi заменяет заданную в командной строке
+        let default_auth = client_options.auth.config();
+        if configs.iter().any(|template| template.config.auth.is_some()) || default_auth.is_some() {
+            let token_client = client::build_client(client_options)?.for_worker(0).clone();
+            let mut providers = AuthProviders::new(token_client);
+            for template in &mut configs {
+                if let Some(auth) = template.config.auth.as_ref().or(default_auth.as_ref()) {
+                    template.auth = Some(providers.get(auth).await?);
+                }
+            }
+        }
+
+        Ok(Self {
             configs,
             distribution,
             current_index: AtomicUsize::new(0),
-        }
+        })
+    }
+
+    // Тестер с единственным URL для режимов burst и rps
+    async fn single(config: RequestConfig, client_options: &ClientOptions) -> Result<Self, String> {
+        Self::new(vec![config], UrlDistribution::RoundRobin, client_options).await
     }
 
-    fn get_next_config(&self, user_id: usize) -> &RequestConfig {
+    fn get_next_config(&self, user_id: usize) -> &RequestTemplate {
         match self.distribution {
             UrlDistribution::RoundRobin => {
                 let index = self.current_index.fetch_add(1, Ordering::SeqCst);
@@ -70,6 +142,14 @@ pub struct MultiUrlConfig {
     #[arg(short = 'f', long)]
     pub config_file: Option<String>,
 
+    /// .env file with variables for ${VAR} in the config file (the environment takes precedence)
+    #[arg(long, requires = "config_file")]
+    pub env_file: Option<String>,
+
+    /// Profile from the config file: its base_url, headers and load settings override the common ones
+    #[arg(long, requires = "config_file")]
+    pub profile: Option<String>,
+
     /// List of URLs to test (comma-separated)
     #[arg(short = 'L', long, value_delimiter = ',')]
     pub url_list: Option<Vec<String>>,
@@ -98,9 +178,42 @@ pub struct MultiUrlConfig {
     #[arg(long, default_value_t = true)]
     pub validate_url: bool,
 
-    /// How to distribute requests between URLs
-    #[arg(long, value_enum, default_value = "round-robin")]
-    pub distribution: UrlDistribution,
+    /// How to distribute requests between URLs (default: round-robin)
+    #[arg(long, value_enum)]
+    pub distribution: Option<UrlDistribution>,
+
+    /// Number of concurrent users in burst mode (default: `users` from the config file or 20)
+    #[arg(short = 'u', long)]
+    pub users: Option<usize>,
+
+    /// Requests per second; switches multi to the RPS scheduler
+    #[arg(short = 'r', long)]
+    pub rps: Option<usize>,
+
+    /// Test duration in seconds
+    #[arg(long)]
+    pub duration: Option<u64>,
+
+    /// Total number of requests across all URLs
+    #[arg(short = 'n', long)]
+    pub total_requests: Option<usize>,
+
+    /// Worker pool size (defaults to the number of users)
+    #[arg(short = 'w', long)]
+    pub workers: Option<usize>,
+
+    /// Print a line for every request
+    #[arg(short = 'v', long, default_value_t = false)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub graphql: GraphqlOptions,
+
+    #[command(flatten)]
+    pub stream: StreamOptions,
+
+    #[command(flatten)]
+    pub client: ClientOptions,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -118,6 +231,12 @@ enum BodyType {
     Text(String),
     Form(HashMap<String, String>),
     Binary(Vec<u8>),
+    // Запрос GraphQL; operation_name - указанное или найденное в документе имя операции
+    GraphQl {
+        query: String,
+        operation_name: Option<String>,
+        variables: Option<Value>,
+    },
     None,
 }
 
@@ -155,6 +274,83 @@ fn parse_body(body_str: &str) -> Result<BodyType, String> {
     Ok(BodyType::Text(body_str.to_string()))
 }
 
+// Body с явно заданным типом; auto угадывает тип через parse_body
+fn parse_body_as(body_str: &str, kind: BodyKind) -> Result<BodyType, String> {
+    match kind {
+        BodyKind::Auto => parse_body(body_str),
+        _ if body_str.is_empty() => Ok(BodyType::None),
+        BodyKind::Text => Ok(BodyType::Text(body_str.to_string())),
+        BodyKind::Base64 => general_purpose::STANDARD.decode(body_str.trim())
+            .map(BodyType::Binary)
+            .map_err(|e| format!("Body не является base64: {}", e)),
+    }
+}
+
+// Body GraphQL из файла запроса
+fn graphql_body(config: &GraphqlConfig) -> Result<BodyType, String> {
+    let query = config.load_query()?;
+    let operation_name = config.operation_name.clone().or_else(|| single_operation_name(&query));
+    Ok(BodyType::GraphQl {
+        query,
+        operation_name,
+        variables: config.variables.clone(),
+    })
+}
+
+// Имя операции, если документ содержит ровно одну именованную операцию.
+// Операции ищутся на верхнем уровне документа, строки и комментарии пропускаются.
+fn single_operation_name(query: &str) -> Option<String> {
+    let mut names = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = query.chars().peekable();
+    let mut previous_keyword = false;
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '(' => depth += 1,
+            '}' | ')' => depth = depth.saturating_sub(1),
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            c if depth == 0 && (c.is_alphabetic() || c == '_') => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                if previous_keyword {
+                    names.push(word.clone());
+                }
+                previous_keyword = matches!(word.as_str(), "query" | "mutation" | "subscription");
+                continue;
+            }
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            previous_keyword = false;
+        }
+    }
+    
+    (names.len() == 1).then(|| names.remove(0))
+}
+
 // Конфигурация запроса
 #[derive(Debug, Clone)]
 struct RequestConfig {
@@ -164,6 +360,20 @@ struct RequestConfig {
     headers: HashMap<String, String>,
     timeout_secs: u64,
     content_type: Option<String>,
+    // TLS-настройки конкретного URL из файла multi
+    tls: Option<TlsOptions>,
+    // Авторизация конкретного URL из файла multi
+    auth: Option<AuthConfig>,
+    // Подпись запросов конкретного URL из файла multi
+    signing: Option<SigningConfig>,
+    // Чтение ответа как потока (--stream или поле `stream` у URL в файле multi)
+    stream: Option<StreamConfig>,
+    // Unix-сокет, через который идут запросы; адрес unix:// задает его прямо в URL
+    socket_path: Option<String>,
+    // Пауза воркера после запроса (think time)
+    think_time: Option<Duration>,
+    // Группа URL в отчете multi
+    group: Option<String>,
 }
 
 impl RequestConfig {
@@ -201,6 +411,136 @@ impl RequestConfig {
             headers: headers_map,
             timeout_secs,
             content_type,
+            tls: None,
+            auth: None,
+            signing: None,
+            stream: None,
+            socket_path: None,
+            think_time: None,
+            group: None,
+        })
+    }
+}
+
+// Кодирует body в байты; второй элемент - Content-Type по умолчанию для этого типа
+fn encode_body(body: &BodyType) -> (Option<Bytes>, Option<&'static str>) {
+    match body {
+        BodyType::Json(json_value) => {
+            (Some(Bytes::from(json_value.to_string())), Some("application/json"))
+        }
+        BodyType::Text(text) => (Some(Bytes::from(text.clone())), None),
+        BodyType::Form(form_data) => {
+            let encoded = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(form_data.iter())
+                .finish();
+            (Some(Bytes::from(encoded)), Some("application/x-www-form-urlencoded"))
+        }
+        BodyType::Binary(data) => (Some(Bytes::from(data.clone())), None),
+        BodyType::GraphQl { query, operation_name, variables } => {
+            let mut request = serde_json::Map::new();
+            request.insert("query".to_string(), Value::String(query.clone()));
+            if let Some(operation_name) = operation_name {
+                request.insert("operationName".to_string(), Value::String(operation_name.clone()));
+            }
+            if let Some(variables) = variables {
+                request.insert("variables".to_string(), variables.clone());
+            }
+            (Some(Bytes::from(Value::Object(request).to_string())), Some("application/json"))
+        }
+        BodyType::None => (None, None),
+    }
+}
+
+// Шаблон запроса, собранный один раз до начала теста.
+// Воркеры разделяют его через Arc и не пересобирают заголовки и body на каждый запрос.
+#[derive(Debug)]
+struct RequestTemplate {
+    config: RequestConfig,
+    method: Method,
+    url: Url,
+    // Ключ статистики: URL, для GraphQL - вместе с именем операции
+    url_str: Arc<str>,
+    headers: HeaderMap,
+    body: Option<Bytes>,
+    timeout: Duration,
+    // Отдельный клиент, если у URL свои TLS-настройки; иначе используется общий
+    client: Option<ClientSet>,
+    // Сколько редиректов проходить; None - не следовать (--no-follow)
+    redirect_limit: Option<usize>,
+    // Заголовок Authorization от провайдера авторизации
+    auth: Option<Authorization>,
+    // Подпись запросов; подпись URL из файла multi заменяет заданную в командной строке
+    signer: Option<Signer>,
+    // Клиент для Unix-сокета; с ним запросы не идут через reqwest
+    unix: Option<UnixClient>,
+}
+
+impl RequestTemplate {
+    async fn new(config: RequestConfig, client_options: &ClientOptions) -> Result<Self, String> {
+        let (socket_path, http_url) = match unix::split_url(&config.url)? {
+            Some((socket_path, http_url)) => (Some(socket_path), http_url),
+            None => (config.socket_path.clone(), config.url.clone()),
+        };
+        let mut url = Url::parse(&http_url)
+            .map_err(|e| format!("Некорректный URL: {}", e))?;
+        let unix = socket_path.as_deref().map(UnixClient::new).transpose()?;
+        if unix.is_none() {
+            dns::check_port(client_options, &url)?;
+        }
+        
+        let signer = match &config.signing {
+            Some(signing) => Some(Signer::new(signing)?),
+            None => client_options.signing.config()?.map(|signing| Signer::new(&signing)).transpose()?,
+        };
+
+        let client = if client::needs_own_client(client_options, config.tls.as_ref()) {
+            Some(client::build_client_for_url(client_options, config.tls.as_ref(), &mut url).await?)
+        } else {
+            None
+        };
+
+        let mut headers = HeaderMap::new();
+        for (key, value) in &config.headers {
+            let name = HeaderName::from_bytes(key.as_bytes())
+                .map_err(|e| format!("Некорректное имя заголовка {}: {}", key, e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| format!("Некорректное значение заголовка {}: {}", key, e))?;
+            headers.append(name, value);
+        }
+
+        if let Some(content_type) = &config.content_type {
+            let value = HeaderValue::from_str(content_type)
+                .map_err(|e| format!("Некорректный Content-Type: {}", e))?;
+            headers.insert(CONTENT_TYPE, value);
+        }
+
+        let (body, default_content_type) = encode_body(&config.body);
+        if let Some(content_type) = default_content_type
+            && !headers.contains_key(CONTENT_TYPE)
+        {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+
+        let url_str = match &config.body {
+            BodyType::GraphQl { operation_name: Some(operation_name), .. } => {
+                format!("{} [{}]", config.url, operation_name)
+            }
+            _ => config.url.clone(),
+        };
+
+        Ok(Self {
+            method: config.method.clone().into(),
+            url_str: Arc::from(mask_secrets(&url_str)),
+            url,
+            headers,
+            body,
+            timeout: Duration::from_secs(config.timeout_secs),
+            client,
+            redirect_limit: client_options.redirect_limit(),
+            auth: None,
+            signer,
+            unix,
+            config,
         })
     }
 }
@@ -212,6 +552,10 @@ impl RequestConfig {
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    
+    /// Сколько секунд ждать текущие запросы после Ctrl+C
+    #[arg(long, global = true, default_value_t = 5)]
+    grace_period: u64,
 }
 
 #[derive(Subcommand)]
@@ -242,6 +586,10 @@ enum Commands {
         #[arg(short = 'c', long)]
         content_type: Option<String>,
         
+        /// Команда curl (например, из DevTools): заменяет -U, -X, -d, -H и -c
+        #[arg(long, conflicts_with_all = ["url", "method", "body", "headers", "content_type"])]
+        from_curl: Option<String>,
+        
         /// Максимальное время ожидания в секундах
         #[arg(short = 't', long, default_value_t = 30)]
         timeout: u64,
@@ -249,6 +597,23 @@ enum Commands {
         /// Валидировать URL перед отправкой
         #[arg(long, default_value_t = true)]
         validate_url: bool,
+        
+        /// Размер пула воркеров (по умолчанию равен числу пользователей)
+        #[arg(short = 'w', long)]
+        workers: Option<usize>,
+        
+        /// Печатать строку на каждый запрос
+        #[arg(short = 'v', long, default_value_t = false)]
+        verbose: bool,
+        
+        #[command(flatten)]
+        graphql: GraphqlOptions,
+        
+        #[command(flatten)]
+        stream: StreamOptions,
+        
+        #[command(flatten)]
+        client: ClientOptions,
     },
     
     /// Симуляция постоянной нагрузки (RPS)
@@ -281,6 +646,10 @@ enum Commands {
         #[arg(short = 'c', long)]
         content_type: Option<String>,
         
+        /// Команда curl (например, из DevTools): заменяет -U, -X, -d, -H и -c
+        #[arg(long, conflicts_with_all = ["url", "method", "body", "headers", "content_type"])]
+        from_curl: Option<String>,
+        
         /// Максимальное время ожидания в секундах
         #[arg(short = 't', long, default_value_t = 30)]
         timeout: u64,
@@ -292,13 +661,35 @@ enum Commands {
         /// Динамические параметры в body (например, {{userId}})
         #[arg(long, default_value_t = false)]
         dynamic_body: bool,
+        
+        /// Условие досрочной остановки, например "error_rate>20% for 10s" или "p99>5s"
+        /// (можно указать несколько)
+        #[arg(long = "abort-on")]
+        abort_on: Vec<AbortCondition>,
+        
+        /// Размер пула воркеров (по умолчанию RPS * 2)
+        #[arg(short = 'w', long)]
+        workers: Option<usize>,
+        
+        /// Печатать строку на каждый запрос
+        #[arg(short = 'v', long, default_value_t = false)]
+        verbose: bool,
+        
+        #[command(flatten)]
+        graphql: GraphqlOptions,
+        
+        #[command(flatten)]
+        stream: StreamOptions,
+        
+        #[command(flatten)]
+        client: ClientOptions,
     },
     
     /// Проверка конфигурации запроса (без отправки)
     Check {
         /// URL бэкенда
-        #[arg(short = 'U', long)]
-        url: String,
+        #[arg(short = 'U', long, required_unless_present_any = ["from_curl", "config_file"])]
+        url: Option<String>,
         
         /// HTTP метод
         #[arg(short = 'X', long, value_enum, default_value = "post")]
@@ -308,204 +699,526 @@ enum Commands {
         #[arg(short = 'd', long)]
         body: Option<String>,
         
+        /// Как читать body: auto угадывает тип, text - строка как есть, base64 - байты
+        #[arg(long, value_enum, default_value = "auto")]
+        body_type: BodyKind,
+        
         /// Заголовки в формате "Header: Value"
         #[arg(short = 'H', long)]
         headers: Vec<String>,
-    },
-    Multi(MultiUrlConfig)
-}
-
-// Результат запроса
-#[derive(Debug)]
-struct RequestResult {
-    user_id: usize,
-    success: bool,
-    duration: Duration,
-    status_code: Option<u16>,
-    error: Option<String>,
-    url: String,
-    //method: String,
-}
-
-// Статистика теста
-#[derive(Debug, Default)]
-struct TestStats {
-    total_requests: usize,
-    successful: usize,
-    failed: usize,
-    min_duration: Duration,
-    max_duration: Duration,
-    total_duration: Duration,
-    avg_duration: Duration,
-    status_codes: HashMap<u16, usize>,
-}
-
-impl TestStats {
-    fn new() -> Self {
-        Self {
-            min_duration: Duration::from_secs(u64::MAX),
-            max_duration: Duration::from_secs(0),
-            ..Default::default()
-        }
-    }
-    
-    fn add_result(&mut self, result: &RequestResult) {
-        self.total_requests += 1;
         
-        if result.success {
-            self.successful += 1;
-            
-            if let Some(status) = result.status_code {
-                *self.status_codes.entry(status).or_insert(0) += 1;
-            }
-            
-            self.total_duration += result.duration;
-            
-            if result.duration < self.min_duration {
-                self.min_duration = result.duration;
-            }
-            if result.duration > self.max_duration {
-                self.max_duration = result.duration;
-            }
-        } else {
-            self.failed += 1;
-        }
-    }
-    
-    fn calculate_final(&mut self) {
-        if self.successful > 0 {
-            self.avg_duration = self.total_duration / self.successful as u32;
-        }
-    }
-    
-    fn print_summary(&self) {
-        println!("\n📊 Результаты теста:");
-        println!("{}", "=".repeat(40));
-        println!("Всего запросов: {}", self.total_requests);
-        println!("Успешно: {}", self.successful);
-        println!("Неудачно: {}", self.failed);
+        /// Команда curl (например, из DevTools): заменяет -U, -X, -d и -H
+        #[arg(long, conflicts_with_all = ["url", "method", "body", "body_type", "headers"])]
+        from_curl: Option<String>,
         
-        if self.total_requests > 0 {
-            println!("Успешность: {:.1}%", 
-                (self.successful as f32 / self.total_requests as f32) * 100.0);
-        }
+        /// Файл конфигурации multi: проверяются все URL после подстановки переменных, секреты выводятся как ***
+        #[arg(short = 'f', long, conflicts_with_all = ["url", "method", "body", "body_type", "headers", "from_curl"])]
+        config_file: Option<String>,
         
-        if !self.status_codes.is_empty() {
-            println!("\n📈 Коды ответа:");
-            let mut codes: Vec<_> = self.status_codes.iter().collect();
-            codes.sort_by_key(|(code, _)| *code);
-            for (code, count) in codes {
-                println!("  {}: {} запросов", code, count);
-            }
-        }
+        /// Файл .env с переменными для ${VAR} в конфигурации (окружение важнее)
+        #[arg(long, requires = "config_file")]
+        env_file: Option<String>,
         
-        if self.successful > 0 {
-            println!("\n⏱️  Время ответа:");
-            println!("  Минимальное: {:.2}ms", self.min_duration.as_millis());
-            println!("  Максимальное: {:.2}ms", self.max_duration.as_millis());
-            println!("  Среднее: {:.2}ms", self.avg_duration.as_millis());
-        }
-    }
+        /// Профиль из файла конфигурации, например staging
+        #[arg(long, requires = "config_file")]
+        profile: Option<String>,
+    },
+    Multi(MultiUrlConfig),
+    /// Нагрузка на WebSocket: N соединений, сообщения с заданной частотой
+    Ws(ws::WsConfig),
+    /// Нагрузка на gRPC: unary и server streaming вызовы по .proto или набору дескрипторов
+    Grpc(grpc::GrpcConfig),
+    /// Нагрузка на TCP: данные с заданной частотой, ожидание ответа по разделителю или длине
+    Tcp(raw::RawConfig),
+    /// Нагрузка на UDP: датаграммы с заданной частотой, при необходимости с ожиданием ответа
+    Udp(raw::RawConfig),
+    /// Воспроизведение журнала доступа (nginx, Apache, JSON lines) на другом стенде
+    Replay(replay::ReplayConfig),
+    /// Импорт запросов из других форматов в конфигурацию multi
+    #[command(subcommand)]
+    Import(import::ImportSource),
 }
 
 async fn make_request(
     client: &Client,
-    config: &RequestConfig,
+    template: &RequestTemplate,
     user_id: usize,
     dynamic_body: bool,
+    verbose: bool,
 ) -> RequestResult {
     let start_time = Instant::now();
-    let timestamp = Utc::now();
-    let method_str = format!("{:?}", config.method).to_uppercase();
     
-    // Подготавливаем body с динамическими значениями
+    // Body с динамическими значениями собирается заново, статический берется из шаблона
     let body = if dynamic_body {
-        prepare_dynamic_body(&config.body, user_id, timestamp)
+        let form_urlencoded = template.headers.get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+        let body = prepare_dynamic_body(&template.config.body, user_id, Utc::now(), form_urlencoded);
+        encode_body(&body).0
     } else {
-        config.body.clone()
+        template.body.clone()
     };
     
-    // Создаем запрос
-    let mut request_builder = client
-        .request(config.method.clone().into(), &config.url)
-        .timeout(Duration::from_secs(config.timeout_secs));
-    
-    // Добавляем заголовки
-    for (key, value) in &config.headers {
-        request_builder = request_builder.header(key, value);
+    // Редиректы проходятся здесь, а не в reqwest, чтобы учитывать их в отчете
+    let mut method = template.method.clone();
+    let mut url = template.url.clone();
+    let mut headers = template.headers.clone();
+    if let Some(auth) = &template.auth {
+        headers.insert(AUTHORIZATION, auth.header());
     }
-    
-    // Добавляем Content-Type если указан
-    if let Some(content_type) = &config.content_type {
-        request_builder = request_builder.header("Content-Type", content_type);
-    }
-    
-    // Добавляем body в зависимости от типа
-    match body {
-        BodyType::Json(json_value) => {
-            request_builder = request_builder.json(&json_value);
+    let mut body = body;
+    let mut redirects = 0;
+    
+    let outcome = loop {
+        // Создаем запрос; таймаут общий на всю цепочку редиректов
+        let mut request = reqwest::Request::new(method.clone(), url.clone());
+        *request.headers_mut() = headers.clone();
+        let remaining = template.timeout.saturating_sub(start_time.elapsed()).max(Duration::from_millis(1));
+        // Таймаут reqwest распространяется и на чтение body, поэтому потоковый ответ
+        // ограничивается только ожиданием заголовков, а паузы в потоке проверяет read_stream
+        if template.config.stream.is_none() {
+            *request.timeout_mut() = Some(remaining);
         }
-        BodyType::Text(text) => {
-            request_builder = request_builder.body(text);
+        if let Some(body) = &body {
+            *request.body_mut() = Some(body.clone().into());
         }
-        BodyType::Form(form_data) => {
-            request_builder = request_builder.form(&form_data);
+        if let Some(signer) = &template.signer {
+            signer.sign(&mut request, body.as_deref());
         }
-        BodyType::Binary(data) => {
-            request_builder = request_builder.body(data);
+        
+        let execute = async {
+            match &template.unix {
+                Some(unix) => unix.execute(request).await,
+                None => client.execute(request).await.map_err(|e| (classify_error(&e), e.to_string())),
+            }
+        };
+        let mut response = match tokio::time::timeout(remaining, execute).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(error)) => break Err(error),
+            Err(_) => break Err((ErrorClass::Timeout, "Таймаут ожидания ответа".to_string())),
+        };
+        
+        let Some(limit) = template.redirect_limit else { break Ok((response, false)) };
+        let Some(next_url) = redirect_location(&response, &url) else { break Ok((response, false)) };
+        if redirects >= limit {
+            break Ok((response, true));
         }
-        BodyType::None => {}
-    }
+        
+        // Дочитываем body, чтобы соединение вернулось в пул
+        while let Ok(Some(_)) = response.chunk().await {}
+        redirects += 1;
+        
+        // 303, а также 301/302 после POST, повторяются как GET без body
+        let status = response.status();
+        if (status == StatusCode::SEE_OTHER && method != Method::HEAD)
+            || (matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND) && method == Method::POST)
+        {
+            method = Method::GET;
+            body = None;
+            headers.remove(CONTENT_TYPE);
+        }
+        // Учетные данные не передаются на другой хост
+        if next_url.host_str() != url.host_str() || next_url.port_or_known_default() != url.port_or_known_default() {
+            for name in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+                headers.remove(name);
+            }
+        }
+        url = next_url;
+    };
+    // Location может содержать токен в query, поэтому адрес маскируется, как url_str
+    let final_url = (redirects > 0).then(|| Arc::from(mask_secrets(url.as_str())));
     
-    // Отправляем запрос
-    match request_builder.send().await {
-        Ok(response) => {
+    match outcome {
+        Ok((mut response, too_many_redirects)) => {
             let duration = start_time.elapsed();
             let status = response.status();
-            let success = status.is_success();
+            // С --no-follow ответ 3xx - ожидаемый результат
+            let mut success = !too_many_redirects && (status.is_success()
+                || template.redirect_limit.is_none() && status.is_redirection());
+            
+            // Потоковый ответ читается до конца потока; ответ GraphQL с массивом errors -
+            // ошибка даже при HTTP 200
+            let mut stream = None;
+            let body_error = match &template.config.stream {
+                Some(settings) if success => {
+                    let (metrics, error) = stream::read_stream(response, settings, start_time, template.timeout).await;
+                    stream = Some(metrics);
+                    error
+                }
+                _ if success && matches!(template.config.body, BodyType::GraphQl { .. }) => {
+                    graphql_errors(response).await.map(|error| (ErrorClass::GraphQl, error))
+                }
+                _ => {
+                    // Дочитываем body, чтобы соединение вернулось в пул
+                    while let Ok(Some(_)) = response.chunk().await {}
+                    None
+                }
+            };
+            success &= body_error.is_none();
+            
+            if verbose {
+                let status_symbol = if success { "✅" } else { "❌" };
+                let redirect_note = match &final_url {
+                    Some(final_url) => format!(" ↪ {} ({})", final_url, redirects),
+                    None => String::new(),
+                };
+                let stream_note = match &stream {
+                    Some(metrics) => format!(" 📡 событий: {}, поток {:.2}ms",
+                        metrics.events, metrics.duration.as_millis()),
+                    None => String::new(),
+                };
+                println!("👤 {} {} {} {} {:.2}ms{}{}", 
+                    user_id, template.method, template.url_str, status_symbol, duration.as_millis(),
+                    redirect_note, stream_note);
+            }
             
-            let status_symbol = if success { "✅" } else { "❌" };
-            println!("👤 {} {} {} {} {:.2}ms", 
-                user_id, method_str, config.url, status_symbol, duration.as_millis());
+            let (body_error_class, body_error) = body_error.unzip();
+            let error_class = body_error_class.or((!success).then_some(ErrorClass::Http));
+            let error = if too_many_redirects {
+                Some(format!("Превышен лимит редиректов ({}), последний ответ HTTP {}", redirects, status))
+            } else if let Some(body_error) = body_error {
+                // Сообщения GraphQL и ошибки потока приходят от сервера и могут повторять секреты запроса
+                Some(mask_secrets(&body_error))
+            } else if !success {
+                Some(format!("HTTP {}", status))
+            } else {
+                None
+            };
             
             RequestResult {
                 user_id,
                 success,
                 duration,
                 status_code: Some(status.as_u16()),
-                error: if !success {
-                    Some(format!("HTTP {}", status))
-                } else {
-                    None
-                },
-                url: config.url.clone(),
+                error,
+                error_class,
+                url: template.url_str.clone(),
+                redirects,
+                final_url,
+                stream,
                 //method: method_str,
             }
         }
-        Err(e) => {
+        Err((error_class, error)) => {
             let duration = start_time.elapsed();
-            println!("👤 {} {} {} ❌ Ошибка: {} {:.2}ms", 
-                user_id, method_str, config.url, e, duration.as_millis());
+            // Текст ошибки reqwest содержит URL, а в нем может быть секрет
+            let error = mask_secrets(&error);
+            if verbose {
+                println!("👤 {} {} {} ❌ Ошибка: {} {:.2}ms", 
+                    user_id, template.method, template.url_str, error, duration.as_millis());
+            }
             
             RequestResult {
                 user_id,
                 success: false,
                 duration,
                 status_code: None,
-                error: Some(e.to_string()),
-                url: config.url.clone(),
+                error_class: Some(error_class),
+                error: Some(error),
+                url: template.url_str.clone(),
+                redirects,
+                final_url,
+                stream: None,
                 //method: method_str,
             }
         }
     }
 }
 
-fn prepare_dynamic_body(body: &BodyType, user_id: usize, timestamp: chrono::DateTime<Utc>) -> BodyType {
+// Сообщение первой ошибки из поля errors ответа GraphQL.
+// Ответ, который не удалось прочитать или разобрать как JSON, тоже считается ошибкой.
+async fn graphql_errors(response: reqwest::Response) -> Option<String> {
+    let body = match response.bytes().await {
+        Ok(body) => body,
+        Err(e) => return Some(format!("Не удалось прочитать ответ GraphQL: {}", e)),
+    };
+    let response: Value = match serde_json::from_slice(&body) {
+        Ok(response) => response,
+        Err(e) => return Some(format!("Ответ GraphQL не является JSON: {}", e)),
+    };
+    
+    let errors = response.get("errors")?.as_array().filter(|errors| !errors.is_empty())?;
+    let message = errors[0].get("message").and_then(Value::as_str).unwrap_or("без сообщения");
+    Some(match errors.len() {
+        1 => format!("GraphQL: {}", message),
+        count => format!("GraphQL: {} (и еще {})", message, count - 1),
+    })
+}
+
+// Адрес редиректа из ответа 3xx с заголовком Location. Относительный адрес разрешается
+// от URL запроса: у ответа через Unix-сокет response.url() - заглушка reqwest
+fn redirect_location(response: &reqwest::Response, request_url: &Url) -> Option<Url> {
+    if !response.status().is_redirection() {
+        return None;
+    }
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    request_url.join(location).ok()
+}
+
+// Сколько неудачных запросов хранить для вывода в отчете
+const FAILURE_SAMPLES: usize = 10;
+
+// Определяет класс ошибки по ее причинам: сообщение верхнего уровня содержит URL
+// и не годится для поиска признаков TLS
+fn classify_error(error: &reqwest::Error) -> ErrorClass {
+    if error.is_timeout() {
+        return ErrorClass::Timeout;
+    }
+    std::error::Error::source(error).and_then(classify_transport).unwrap_or(if error.is_connect() {
+        ErrorClass::Connect
+    } else {
+        ErrorClass::Other
+    })
+}
+
+// Источник заданий для пула воркеров
+enum JobSource {
+    // Фиксированное число запросов (burst, multi), при наличии deadline - не дольше него
+    Counter { next: AtomicUsize, total: usize, deadline: Option<Instant> },
+    // Запросы, выдаваемые планировщиком RPS
+    Scheduled(Mutex<mpsc::Receiver<usize>>),
+}
+
+impl JobSource {
+    fn counter(total: usize) -> Self {
+        JobSource::Counter { next: AtomicUsize::new(1), total, deadline: None }
+    }
+
+    // Запросы выдаются до истечения duration, но не больше total
+    fn timed(total: usize, duration: Duration) -> Self {
+        JobSource::Counter {
+            next: AtomicUsize::new(1),
+            total,
+            deadline: Some(Instant::now() + duration),
+        }
+    }
+
+    // После остановки теста новые задания не выдаются
+    async fn next(&self, stop: &StopSignal) -> Option<usize> {
+        if stop.is_stopped() {
+            return None;
+        }
+        
+        match self {
+            JobSource::Counter { next, total, deadline } => {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return None;
+                }
+                let user_id = next.fetch_add(1, Ordering::Relaxed);
+                (user_id <= *total).then_some(user_id)
+            }
+            JobSource::Scheduled(receiver) => {
+                let mut receiver = receiver.lock().await;
+                tokio::select! {
+                    user_id = receiver.recv() => user_id,
+                    _ = stop.stopped() => None,
+                }
+            }
+        }
+    }
+}
+
+// Параметры запуска, общие для burst, rps и multi
+struct RunOptions {
+    client_options: ClientOptions,
+    workers: usize,
+    should_validate_url: bool,
+    dynamic_body: bool,
+    verbose: bool,
+    // Сколько ждать запросы в полете после остановки теста
+    grace_period: Duration,
+    // Условия досрочной остановки (--abort-on)
+    abort_conditions: Vec<AbortCondition>,
+    stop_handle: StopHandle,
+    stop: StopSignal,
+}
+
+// Общие данные всех воркеров пула
+struct WorkerContext {
+    client: ClientSet,
+    tester: Arc<MultiUrlTester>,
+    jobs: JobSource,
+    dynamic_body: bool,
+    verbose: bool,
+    stop: StopSignal,
+}
+
+// Запускает пул воркеров; результаты отправляются в канал по мере готовности
+fn spawn_workers(
+    context: Arc<WorkerContext>,
+    workers: usize,
+    results: mpsc::Sender<RequestResult>,
+) -> Vec<JoinHandle<()>> {
+    (0..workers.max(1))
+        .map(|worker| {
+            let context = context.clone();
+            let results = results.clone();
+            
+            tokio::spawn(async move {
+                while let Some(user_id) = context.jobs.next(&context.stop).await {
+                    let template = context.tester.get_next_config(user_id);
+                    let client = template.client.as_ref()
+                        .unwrap_or(&context.client)
+                        .for_worker(worker);
+                    let result = make_request(
+                        client, template, user_id, context.dynamic_body, context.verbose
+                    ).await;
+                    
+                    if results.send(result).await.is_err() {
+                        break;
+                    }
+                    
+                    if let Some(think_time) = template.config.think_time {
+                        tokio::select! {
+                            _ = tokio::time::sleep(think_time) => {}
+                            _ = context.stop.stopped() => break,
+                        }
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+// Ожидает завершения воркеров и сообщает об упавших задачах.
+// После остановки теста ждет не дольше grace_period, затем отменяет оставшиеся запросы.
+async fn join_workers(handles: Vec<JoinHandle<()>>, stop: &StopSignal, grace_period: Duration) {
+    let abort_handles: Vec<_> = handles.iter().map(|handle| handle.abort_handle()).collect();
+    
+    let join_all = async {
+        for handle in handles {
+            if let Err(e) = handle.await
+                && !e.is_cancelled()
+            {
+                eprintln!("Ошибка в задаче: {}", e);
+            }
+        }
+    };
+    let deadline = async {
+        stop.stopped().await;
+        tokio::time::sleep(grace_period).await;
+    };
+    
+    tokio::select! {
+        _ = join_all => {}
+        _ = deadline => {
+            eprintln!("⚠️  Период ожидания истек, незавершенные запросы отменены");
+            for handle in abort_handles {
+                handle.abort();
+            }
+        }
+    }
+}
+
+// Запускает пул воркеров вместе с агрегатором и ждет окончания теста
+async fn run_worker_pool(
+    tester: Arc<MultiUrlTester>,
+    jobs: JobSource,
+    options: &RunOptions,
+    live_report: bool,
+) -> Result<Aggregator, Box<dyn std::error::Error>> {
+    let context = Arc::new(WorkerContext {
+        client: client::build_client(&options.client_options)?,
+        tester,
+        jobs,
+        dynamic_body: options.dynamic_body,
+        verbose: options.verbose,
+        stop: options.stop.clone(),
+    });
+    
+    let (results_tx, results_rx) = mpsc::channel(options.workers.max(1) * 2);
+    let guardrails = Guardrails::new(options.abort_conditions.clone(), options.stop_handle.clone());
+    let aggregator = spawn_aggregator(results_rx, FAILURE_SAMPLES, live_report, guardrails);
+    let handles = spawn_workers(context, options.workers, results_tx);
+    
+    join_workers(handles, &options.stop, options.grace_period).await;
+    Ok(aggregator.await?)
+}
+
+// Планировщик RPS: выдает user_id с равными интервалами, воркеры их разбирают.
+// Останавливается, выдав total заданий, или при остановке теста.
+fn spawn_rps_scheduler(rps: usize, total: usize, stop: StopSignal) -> (JobSource, JoinHandle<()>) {
+    let (jobs_tx, jobs_rx) = mpsc::channel(rps.max(1));
+    
+    let scheduler = tokio::spawn(async move {
+        let period = (Duration::from_secs(1) / rps.max(1) as u32).max(Duration::from_nanos(1));
+        let mut ticker = tokio::time::interval(period);
+        let schedule = async {
+            for user_id in 1..=total {
+                ticker.tick().await;
+                if jobs_tx.send(user_id).await.is_err() {
+                    break;
+                }
+            }
+        };
+        
+        tokio::select! {
+            _ = schedule => {}
+            _ = stop.stopped() => {}
+        }
+    });
+    
+    (JobSource::Scheduled(Mutex::new(jobs_rx)), scheduler)
+}
+
+// Задания для режимов с параметрами -c/-n/-r/--duration (grpc, tcp, udp).
+// С RPS - планировщик; без явного числа запросов тест идет duration секунд (по умолчанию 10).
+// Без RPS - счетчик: total запросов (по умолчанию concurrency), с duration - до истечения времени.
+fn load_jobs(
+    rps: Option<usize>,
+    total: Option<usize>,
+    duration: Option<u64>,
+    concurrency: usize,
+    stop: &StopSignal,
+) -> Result<(JobSource, Option<JoinHandle<()>>), String> {
+    match rps {
+        Some(0) => Err("RPS должен быть больше нуля".to_string()),
+        Some(rps) => {
+            let planned = rps * duration.unwrap_or(10) as usize;
+            let total = match (total, duration) {
+                (Some(total), None) => total,
+                (Some(total), Some(_)) => total.min(planned),
+                (None, _) => planned,
+            };
+            let (jobs, scheduler) = spawn_rps_scheduler(rps, total, stop.clone());
+            Ok((jobs, Some(scheduler)))
+        }
+        None => {
+            let jobs = match duration {
+                Some(duration) => JobSource::timed(total.unwrap_or(usize::MAX), Duration::from_secs(duration)),
+                None => JobSource::counter(total.unwrap_or(concurrency)),
+            };
+            Ok((jobs, None))
+        }
+    }
+}
+
+// Помечает отчет, если тест был остановлен досрочно
+fn print_stop_notice(stop: &StopSignal) {
+    if let Some(reason) = stop.reason() {
+        println!("\n⚠️  ТЕСТ ОСТАНОВЛЕН ДОСРОЧНО: {}", reason);
+        println!("   Отчет построен по результатам, собранным до остановки");
+    }
+}
+
+// Подставляет {{userId}}, {{timestamp}} и {{uuid}}. Текстовое тело формы (form_urlencoded)
+// получает значения в кодировке формы: иначе + в часовом поясе timestamp сервер прочитает как пробел
+fn prepare_dynamic_body(
+    body: &BodyType,
+    user_id: usize,
+    timestamp: chrono::DateTime<Utc>,
+    form_urlencoded: bool,
+) -> BodyType {
     match body {
         BodyType::Text(text) => {
+            let encode = |value: String| if form_urlencoded {
+                url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+            } else {
+                value
+            };
             let replaced = text
                 .replace("{{userId}}", &user_id.to_string())
-                .replace("{{timestamp}}", &timestamp.to_rfc3339())
+                .replace("{{timestamp}}", &encode(timestamp.to_rfc3339()))
                 .replace("{{uuid}}", &uuid::Uuid::new_v4().to_string());
             BodyType::Text(replaced)
         }
@@ -514,8 +1227,9 @@ fn prepare_dynamic_body(body: &BodyType, user_id: usize, timestamp: chrono::Date
             let replaced = json_str
                 .replace("\"{{userId}}\"", &user_id.to_string())
                 .replace("{{userId}}", &user_id.to_string())
-                .replace("{{timestamp}}", &format!("\"{}\"", timestamp.to_rfc3339()))
-                .replace("{{uuid}}", &format!("\"{}\"", uuid::Uuid::new_v4()));
+                // Разобранный JSON содержит подстановки только внутри строк
+                .replace("{{timestamp}}", &timestamp.to_rfc3339())
+                .replace("{{uuid}}", &uuid::Uuid::new_v4().to_string());
             
             match serde_json::from_str::<Value>(&replaced) {
                 Ok(new_json) => BodyType::Json(new_json),
@@ -527,16 +1241,69 @@ fn prepare_dynamic_body(body: &BodyType, user_id: usize, timestamp: chrono::Date
             for (key, value) in form_data {
                 let new_value = value
                     .replace("{{userId}}", &user_id.to_string())
-                    .replace("{{timestamp}}", &timestamp.to_rfc3339());
+                    .replace("{{timestamp}}", &timestamp.to_rfc3339())
+                    .replace("{{uuid}}", &uuid::Uuid::new_v4().to_string());
                 new_form.insert(key.clone(), new_value);
             }
             BodyType::Form(new_form)
         }
+        // Двоичное body (multipart из импорта) - подстановки в текстовых частях
+        BodyType::Binary(data) => {
+            let replaced = [
+                ("{{userId}}", user_id.to_string()),
+                ("{{timestamp}}", timestamp.to_rfc3339()),
+                ("{{uuid}}", uuid::Uuid::new_v4().to_string()),
+            ]
+            .iter()
+            .fold(data.clone(), |data, (placeholder, value)| replace_bytes(&data, placeholder.as_bytes(), value.as_bytes()));
+            BodyType::Binary(replaced)
+        }
+        BodyType::GraphQl { query, operation_name, variables } => {
+            let variables = variables.as_ref().map(|variables| {
+                match prepare_dynamic_body(&BodyType::Json(variables.clone()), user_id, timestamp, false) {
+                    BodyType::Json(variables) => variables,
+                    _ => variables.clone(),
+                }
+            });
+            BodyType::GraphQl {
+                query: query.clone(),
+                operation_name: operation_name.clone(),
+                variables,
+            }
+        }
         other => other.clone(),
     }
 }
 
+fn replace_bytes(data: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut rest = data;
+    while let Some(index) = rest.windows(from.len()).position(|window| window == from) {
+        result.extend_from_slice(&rest[..index]);
+        result.extend_from_slice(to);
+        rest = &rest[index + from.len()..];
+    }
+    result.extend_from_slice(rest);
+    result
+}
+
+// Запрос из команды curl для burst и rps; -k из curl отключает проверку сертификата
+fn curl_request_config(
+    command: &str,
+    timeout_secs: u64,
+    client_options: &mut ClientOptions,
+) -> Result<RequestConfig, String> {
+    let curl = CurlRequest::parse(command)?;
+    println!("💡 Проверить запрос: {}", curl.check_invocation());
+    client_options.tls.insecure |= curl.insecure;
+    curl.request_config(timeout_secs)
+}
+
 fn validate_url(url: &str) -> Result<(), String> {
+    // unix:///path/app.sock:/api - HTTP через Unix-сокет
+    if unix::split_url(url)?.is_some() {
+        return Ok(());
+    }
     Url::parse(url)
         .map_err(|e| format!("Некорректный URL: {}", e))
         .and_then(|parsed| {
@@ -551,10 +1318,9 @@ fn validate_url(url: &str) -> Result<(), String> {
 async fn simulate_burst(
     config: RequestConfig,
     users: usize,
-    should_validate_url: bool,
-    dynamic_body: bool,
+    options: &RunOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if should_validate_url {
+    if options.should_validate_url {
         validate_url(&config.url)?;
     }
     
@@ -562,6 +1328,7 @@ async fn simulate_burst(
     println!("🌐 Метод: {:?}", config.method);
     println!("🔗 URL: {}", config.url);
     println!("⏱️  Таймаут: {} секунд", config.timeout_secs);
+    println!("👷 Воркеров: {}", options.workers);
     
     if !config.headers.is_empty() {
         println!("📋 Заголовки:");
@@ -575,58 +1342,29 @@ async fn simulate_burst(
         BodyType::Text(text) => println!("📦 Body (текст): {}", text),
         BodyType::Form(form) => println!("📦 Body (form): {:?}", form),
         BodyType::Binary(data) => println!("📦 Body (binary): {} байт", data.len()),
+        BodyType::GraphQl { operation_name, .. } => {
+            println!("📦 Body (GraphQL): {}", operation_name.as_deref().unwrap_or("операция без имени"));
+        }
         BodyType::None => println!("📦 Body: нет"),
     }
     
     println!("{}", "=".repeat(50));
     
-    let client = Client::new();
+    let tester = Arc::new(MultiUrlTester::single(config, &options.client_options).await?);
     let start_time = Instant::now();
     
-    // Создаем задачи для всех пользователей
-    let tasks: Vec<_> = (1..=users)
-        .map(|user_id| {
-            let client = client.clone();
-            let config = config.clone();
-            
-            tokio::spawn(async move {
-                make_request(&client, &config, user_id, dynamic_body).await
-            })
-        })
-        .collect();
-    
-    // Ждем завершения всех задач
-    let results = join_all(tasks).await;
+    let report = run_worker_pool(tester, JobSource::counter(users), options, false).await?;
     
-    // Обрабатываем результаты
-    let mut stats = TestStats::new();
-    let mut all_results = Vec::new();
-    
-    for result in results {
-        match result {
-            Ok(request_result) => {
-                stats.add_result(&request_result);
-                all_results.push(request_result);
-            }
-            Err(e) => {
-                eprintln!("Ошибка в задаче: {}", e);
-            }
-        }
-    }
-    
-    stats.calculate_final();
-    stats.print_summary();
+    print_stop_notice(&options.stop);
+    report.stats.print_summary();
+    report.print_redirects();
     
     let total_duration = start_time.elapsed();
+    report.print_streams(total_duration);
     println!("\n⏰ Общее время теста: {:.2} секунд", total_duration.as_secs_f32());
     
     // Детали по неудачным запросам
-    if stats.failed > 0 {
-        println!("\n🔍 Неудачные запросы (первые 5):");
-        for result in all_results.iter().filter(|r| !r.success).take(5) {
-            println!("  Пользователь {}: {}", result.user_id, result.error.as_deref().unwrap_or("Unknown"));
-        }
-    }
+    report.print_failures();
     
     Ok(())
 }
@@ -635,100 +1373,45 @@ async fn simulate_rps(
     config: RequestConfig,
     rps: usize,
     duration_secs: u64,
-    should_validate_url: bool,
-    dynamic_body: bool,
+    options: &RunOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if should_validate_url {
+    if options.should_validate_url {
         validate_url(&config.url)?;
     }
+    if rps == 0 {
+        return Err("RPS должен быть больше нуля".into());
+    }
     
     println!("📈 Симуляция {} RPS в течение {} секунд", rps, duration_secs);
     println!("🌐 Метод: {:?}", config.method);
     println!("🔗 URL: {}", config.url);
     println!("⏱️  Таймаут: {} секунд", config.timeout_secs);
+    println!("👷 Воркеров: {}", options.workers);
     println!("{}", "=".repeat(50));
     
-    let client = Client::new();
-    let semaphore = Arc::new(Semaphore::new(rps * 2));
-    
-    let mut global_stats = TestStats::new();
-    let mut total_requests = 0;
-    
+    let tester = Arc::new(MultiUrlTester::single(config, &options.client_options).await?);
     let test_start = Instant::now();
+    let total_planned = rps * duration_secs as usize;
     
-    for second in 0..duration_secs {
-        let second_start = Instant::now();
-        let batch_start_user = total_requests + 1;
-        
-        println!("\n🕒 Секунда {}:", second + 1);
-        
-        // Создаем задачи для текущей секунды
-        let mut batch_tasks = Vec::new();
-        
-        for i in 0..rps {
-            let client = client.clone();
-            let config = config.clone();
-            let semaphore = semaphore.clone();
-            let user_id = batch_start_user + i;
-            
-            let task = tokio::spawn(async move {
-                let _permit = semaphore.acquire().await.expect("Semaphore error");
-                make_request(&client, &config, user_id, dynamic_body).await
-            });
-            
-            batch_tasks.push(task);
-            total_requests += 1;
-        }
-        
-        // Ждем завершения всех задач в этой секунде
-        let batch_results = join_all(batch_tasks).await;
-        
-        // Собираем статистику по батчу
-        let mut batch_successful = 0;
-        let mut batch_duration_total = Duration::ZERO;
-        
-        for result in batch_results {
-            match result {
-                Ok(request_result) => {
-                    global_stats.add_result(&request_result);
-                    if request_result.success {
-                        batch_successful += 1;
-                        batch_duration_total += request_result.duration;
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Ошибка в задаче: {}", e);
-                    global_stats.failed += 1;
-                }
-            }
-        }
-        
-        // Выводим статистику за секунду
-        println!("  Запросов: {}/{} успешно", batch_successful, rps);
-        if batch_successful > 0 {
-            let avg_duration = batch_duration_total / batch_successful as u32;
-            println!("  Среднее время: {:.2}ms", avg_duration.as_millis());
-        }
-        
-        // Ждем до конца секунды, если задачи выполнились быстрее
-        let elapsed = second_start.elapsed();
-        if elapsed < Duration::from_secs(1) {
-            let sleep_time = Duration::from_secs(1) - elapsed;
-            tokio::time::sleep(sleep_time).await;
-        }
+    let (jobs, scheduler) = spawn_rps_scheduler(rps, total_planned, options.stop.clone());
+    let report = run_worker_pool(tester, jobs, options, true).await?;
+    if let Err(e) = scheduler.await {
+        eprintln!("Ошибка в задаче: {}", e);
     }
-    
-    global_stats.calculate_final();
+    let global_stats = &report.stats;
     
     println!("\n{}", "=".repeat(50));
     println!("🎯 ИТОГИ ТЕСТА:");
+    print_stop_notice(&options.stop);
     global_stats.print_summary();
+    report.print_redirects();
     
     let total_test_duration = test_start.elapsed();
+    report.print_streams(total_test_duration);
     println!("\n⏰ Общее время теста: {:.2} секунд", 
         total_test_duration.as_secs_f32());
     
-    let actual_rps = total_requests as f32 / duration_secs as f32;
+    let actual_rps = global_stats.total_requests as f32 / total_test_duration.as_secs_f32();
     println!("📊 Фактический RPS: {:.1}", actual_rps);
     
     Ok(())
@@ -738,13 +1421,84 @@ fn check_config(
     url: String,
     method: HttpMethod,
     body_str: Option<String>,
+    body_kind: BodyKind,
     headers: Vec<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("🔍 Проверка конфигурации запроса:");
     println!("{}", "=".repeat(40));
     
+    check_request(&url, method, body_str.as_deref(), body_kind, &headers);
+    
+    println!("\n💡 Примеры использования:");
+    println!("  burst -U https://api.example.com/users -X GET");
+    println!("  burst -U https://api.example.com/users -X POST -d '{{\"name\":\"John\"}}'");
+    println!("  burst -U https://api.example.com/login -X POST -d 'username=admin&password=123'");
+    println!("  burst -U https://api.example.com/upload -X PUT -d 'SGVsbG8gV29ybGQ=' -H 'Authorization: Bearer token'");
+    
+    Ok(())
+}
+
+// Проверка файла multi: все URL после подстановки переменных; значения секретов и переменных
+// с именами вроде TOKEN и PASSWORD скрыты
+fn check_config_file(path: &str, env: &ConfigEnv, profile: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let test_config = MultiTestConfig::load(path, env, profile)?;
+    warn_unmasked_secrets();
+    println!("🔍 Проверка файла конфигурации: {}", path);
+    if let Some(name) = &test_config.name {
+        println!("📛 Название: {}", name);
+    }
+    if let Some(profile) = profile {
+        println!("🏷️  Профиль: {}", profile);
+    }
+    if let Some(base_url) = &test_config.base_url {
+        println!("🔗 base_url: {}", mask_secrets(base_url));
+    }
+    println!("{}", "=".repeat(40));
+    
+    for (i, url_config) in test_config.urls.iter().enumerate() {
+        let group = url_config.group.as_deref().map(|group| format!(" (группа: {})", group)).unwrap_or_default();
+        println!("\n{}.{}", i + 1, group);
+        let mut headers: Vec<String> = Vec::new();
+        for source in [&test_config.common_headers, &url_config.headers].into_iter().flatten() {
+            headers.extend(source.iter().map(|(key, value)| mask_secrets(&format!("{}: {}", key, value))));
+        }
+        let body = url_config.body.as_ref().or(test_config.common_body.as_ref())
+            .map(|body| mask_secrets(body));
+        check_request(
+            &mask_secrets(&url_config.url),
+            url_config.method.clone().unwrap_or(HttpMethod::GET),
+            body.as_deref(),
+            url_body_kind(url_config),
+            &headers,
+        );
+        if let Some(auth) = &url_config.auth {
+            let kind = match auth {
+                AuthConfig::Basic { .. } => "basic",
+                AuthConfig::Bearer { .. } => "bearer",
+                AuthConfig::OAuth2 { .. } => "oauth2",
+            };
+            println!("🔐 Авторизация: {}", kind);
+        }
+    }
+    Ok(())
+}
+
+fn warn_unmasked_secrets() {
+    let sources = unmasked_secrets();
+    if !sources.is_empty() {
+        eprintln!("⚠️  Значения короче {} символов не скрываются в выводе: {}", MIN_SECRET_LEN, sources.join(", "));
+    }
+}
+
+// Переменные для ${VAR}: окружение и необязательный файл .env
+fn config_env(env_file: Option<&str>) -> Result<ConfigEnv, String> {
+    env_file.map_or_else(|| Ok(ConfigEnv::default()), ConfigEnv::from_file)
+}
+
+// Строки проверки URL, метода, body и заголовков
+fn check_request(url: &str, method: HttpMethod, body_str: Option<&str>, body_kind: BodyKind, headers: &[String]) {
     // Валидация URL
-    match validate_url(&url) {
+    match validate_url(url) {
         Ok(_) => println!("✅ URL: {}", url),
         Err(e) => println!("❌ URL: {} - {}", url, e),
     }
@@ -753,7 +1507,7 @@ fn check_config(
     
     // Парсинг и валидация body
     if let Some(body_str) = body_str {
-        match parse_body(&body_str) {
+        match parse_body_as(body_str, body_kind) {
             Ok(body_type) => {
                 println!("✅ Body распознан как:");
                 match body_type {
@@ -766,6 +1520,7 @@ fn check_config(
                         }
                     }
                     BodyType::Binary(data) => println!("   Бинарные данные ({} байт)", data.len()),
+                    BodyType::GraphQl { .. } => println!("   Запрос GraphQL"),
                     BodyType::None => println!("   Нет body"),
                 }
             }
@@ -787,136 +1542,107 @@ fn check_config(
             }
         }
     }
-    
-    println!("\n💡 Примеры использования:");
-    println!("  burst -U https://api.example.com/users -X GET");
-    println!("  burst -U https://api.example.com/users -X POST -d '{{\"name\":\"John\"}}'");
-    println!("  burst -U https://api.example.com/login -X POST -d 'username=admin&password=123'");
-    println!("  burst -U https://api.example.com/upload -X PUT -d 'SGVsbG8gV29ybGQ=' -H 'Authorization: Bearer token'");
-    
-    Ok(())
+}
+
+// Режим нагрузки для multi
+#[derive(Debug)]
+enum MultiLoad {
+    // `users` воркеров отправляют запросы друг за другом
+    Burst {
+        users: usize,
+        total_requests: usize,
+        duration: Option<Duration>,
+    },
+    // Запросы выдаются планировщиком с постоянной частотой
+    Rps {
+        rps: usize,
+        total_requests: usize,
+    },
 }
 
 async fn simulate_multiple_urls(
     tester: Arc<MultiUrlTester>,
-    users: usize,
-    should_validate_url: bool,
-    dynamic_body: bool,
-) -> Result<TestStats, Box<dyn std::error::Error>> {
-    println!("🚀 Запуск {} запросов на {} URL", users, tester.configs.len());
+    load: MultiLoad,
+    options: &RunOptions,
+) -> Result<(TestStats, Duration), Box<dyn std::error::Error>> {
+    match &load {
+        MultiLoad::Burst { users, total_requests, duration } => {
+            print!("🚀 Запуск {} пользователей на {} URL", users, tester.configs.len());
+            if *total_requests != usize::MAX {
+                print!(", всего запросов: {}", total_requests);
+            }
+            if let Some(duration) = duration {
+                print!(", длительность: {} секунд", duration.as_secs());
+            }
+            println!();
+        }
+        MultiLoad::Rps { rps, total_requests } => {
+            println!("📈 Симуляция {} RPS на {} URL, всего запросов: {}",
+                rps, tester.configs.len(), total_requests);
+        }
+    }
+    println!("👷 Воркеров: {}", options.workers);
     
     // Валидация всех URL
-    if should_validate_url {
-        for config in &tester.configs {
-            validate_url(&config.url)?;
+    if options.should_validate_url {
+        for template in &tester.configs {
+            validate_url(&template.config.url)?;
         }
     }
     
     // Вывод информации о URL
     println!("\n📋 Тестируемые URL:");
-    for (i, config) in tester.configs.iter().enumerate() {
-        println!("  {}: {} (метод: {:?})", i + 1, config.url, config.method);
+    for (i, template) in tester.configs.iter().enumerate() {
+        let group = template.config.group.as_deref()
+            .map(|group| format!(", группа: {}", group))
+            .unwrap_or_default();
+        println!("  {}: {} (метод: {:?}{})", i + 1, template.url_str, template.config.method, group);
     }
     
     println!("📊 Распределение запросов: {:?}", tester.distribution);
     println!("{}", "=".repeat(50));
     
-    let client = Client::new();
-    let start_time = Instant::now();
-    
-    // Создаем задачи для всех пользователей
-    let tasks: Vec<_> = (1..=users)
-        .map(|user_id| {
-            let client = client.clone();
-            let tester = tester.clone();
-            
-            tokio::spawn(async move {
-                let config = tester.get_next_config(user_id);
-                make_request(&client, config, user_id, dynamic_body).await
-            })
-        })
+    let groups: HashMap<Arc<str>, String> = tester.configs.iter()
+        .filter_map(|template| Some((template.url_str.clone(), template.config.group.clone()?)))
         .collect();
+    let start_time = Instant::now();
     
-    // Ждем завершения всех задач
-    let results = join_all(tasks).await;
-    
-    // Обрабатываем результаты
-    let mut stats = TestStats::new();
-    let mut all_results = Vec::new();
-    
-    for result in results {
-        match result {
-            Ok(request_result) => {
-                stats.add_result(&request_result);
-                all_results.push(request_result);
-            }
-            Err(e) => {
+    let report = match load {
+        MultiLoad::Burst { total_requests, duration, .. } => {
+            let jobs = match duration {
+                Some(duration) => JobSource::timed(total_requests, duration),
+                None => JobSource::counter(total_requests),
+            };
+            run_worker_pool(tester, jobs, options, false).await?
+        }
+        MultiLoad::Rps { rps, total_requests } => {
+            let (jobs, scheduler) = spawn_rps_scheduler(rps, total_requests, options.stop.clone());
+            let report = run_worker_pool(tester, jobs, options, true).await?;
+            if let Err(e) = scheduler.await {
                 eprintln!("Ошибка в задаче: {}", e);
             }
+            report
         }
-    }
-    
-    stats.calculate_final();
+    };
     
     // Выводим сводную статистику
     println!("\n{}", "=".repeat(50));
     println!("📊 СВОДНАЯ СТАТИСТИКА:");
-    stats.print_summary();
+    print_stop_notice(&options.stop);
+    report.stats.print_summary();
     
     // Детальная статистика по каждому URL
-    println!("\n📈 Статистика по URL:");
-    println!("{}", "-".repeat(40));
-    
-    let mut url_stats: HashMap<String, (usize, usize, Duration)> = HashMap::new(); // (успешно, всего, суммарное время)
-    
-    for result in &all_results {
-        let entry = url_stats.entry(result.url.clone()).or_insert((0, 0, Duration::ZERO));
-        entry.1 += 1; // всего запросов
-        if result.success {
-            entry.0 += 1; // успешных
-            entry.2 += result.duration; // суммарное время
-        }
-    }
-    
-    for (url, (successful, total, total_duration)) in url_stats {
-        let success_rate = if total > 0 {
-            (successful as f32 / total as f32) * 100.0
-        } else {
-            0.0
-        };
-        
-        let avg_duration = if successful > 0 {
-            total_duration / successful as u32
-        } else {
-            Duration::ZERO
-        };
-        
-        println!("🔗 {}", url);
-        println!("   Запросов: {}/{} успешно ({:.1}%)", successful, total, success_rate);
-        if successful > 0 {
-            println!("   Среднее время: {:.2}ms", avg_duration.as_millis());
-        }
-        println!();
-    }
+    report.print_url_summary();
+    report.print_group_summary(&groups);
     
     let total_duration = start_time.elapsed();
+    report.print_streams(total_duration);
     println!("⏰ Общее время теста: {:.2} секунд", total_duration.as_secs_f32());
     
-    // Детали по неудачным запросам - теперь у нас есть URL в результатах
-    if stats.failed > 0 {
-        println!("\n🔍 Неудачные запросы (первые 10):");
-        let failed_results: Vec<_> = all_results.iter()
-            .filter(|r| !r.success)
-            .take(10)
-            .collect();
-        
-        for result in failed_results {
-            println!("  Пользователь {} ({}): {}", 
-                result.user_id, result.url, result.error.as_deref().unwrap_or("Unknown"));
-        }
-    }
+    // Детали по неудачным запросам
+    report.print_failures();
     
-    Ok(stats)
+    Ok((report.stats, total_duration))
 }
 
 fn create_configs_from_urls(
@@ -944,47 +1670,72 @@ fn create_configs_from_urls(
     Ok(configs)
 }
 
-fn load_configs_from_file(
-    file_path: &str,
-    common_headers: Vec<String>,
-    common_timeout: u64,
-) -> Result<Vec<RequestConfig>, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(file_path)?;
-    //let extension = file_path.split('.').last().unwrap_or("").to_lowercase();
-    
-    // Здесь можно добавить парсинг JSON/YAML/TOML
-    // Для простоты будем считать, что файл содержит URL по одному на строку
-    let urls: Vec<String> = content.lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty() && !line.starts_with('#'))
-        .map(|line| line.to_string())
-        .collect();
-    
-    if urls.is_empty() {
-        return Err("No URLs found in config file".into());
+// Тип body записи файла; body_type относится только к собственному body URL,
+// common_body и body из CLI читаются как auto
+fn url_body_kind(url_config: &UrlConfig) -> BodyKind {
+    match url_config.body {
+        Some(_) => url_config.body_type.unwrap_or_default(),
+        None => BodyKind::Auto,
     }
-    
-    // Создаем конфигурации для каждого URL
-    let configs = urls.into_iter()
-        .map(|url| {
-            RequestConfig::from_cli(
-                url,
-                HttpMethod::GET, // По умолчанию GET
-                None,
-                common_headers.clone(),
-                common_timeout,
+}
+
+// Превращает записи файла конфигурации в RequestConfig.
+// Заголовки применяются в порядке: common_headers, заголовки URL, заголовки из CLI.
+fn configs_from_test_config(
+    test_config: &MultiTestConfig,
+    cli: &MultiUrlConfig,
+) -> Result<Vec<RequestConfig>, String> {
+    let cli_graphql = cli.graphql.config()?;
+    test_config.urls.iter()
+        .map(|url_config| {
+            let mut headers: Vec<String> = Vec::new();
+            for source in [&test_config.common_headers, &url_config.headers].into_iter().flatten() {
+                headers.extend(source.iter().map(|(key, value)| format!("{}: {}", key, value)));
+            }
+            headers.extend(cli.headers.iter().cloned());
+            
+            let body = url_config.body.clone()
+                .or_else(|| test_config.common_body.clone())
+                .or_else(|| cli.body.clone());
+            
+            let mut config = RequestConfig::from_cli(
+                url_config.url.clone(),
+                url_config.method.clone().unwrap_or(cli.method.clone()),
                 None,
-            )
+                headers,
+                url_config.timeout.unwrap_or(cli.timeout),
+                cli.content_type.clone(),
+            )?;
+            if let Some(body) = body {
+                config.body = parse_body_as(&body, url_body_kind(url_config))?;
+            }
+            config.tls = url_config.tls.clone();
+            config.auth = url_config.auth.clone();
+            config.signing = url_config.sign.clone();
+            config.stream = url_config.stream.clone().or_else(|| cli.stream.config());
+            config.socket_path = url_config.socket_path.clone();
+            config.think_time = url_config.think_time_ms.map(Duration::from_millis);
+            config.group = url_config.group.clone();
+            // GraphQL URL заменяет body; GraphQL из CLI - только для URL без своего body
+            if let Some(graphql) = &url_config.graphql {
+                config.body = graphql_body(graphql)?;
+            } else if url_config.body.is_none() && let Some(graphql) = &cli_graphql {
+                config.body = graphql_body(graphql)?;
+            }
+            Ok(config)
         })
-        .collect::<Result<Vec<_>, _>>()?;
-    
-    Ok(configs)
+        .collect()
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
     
+    // Первый Ctrl+C останавливает тест и печатает частичный отчет
+    let grace_period = Duration::from_secs(cli.grace_period);
+    let (stop_handle, stop) = stop_channel();
+    spawn_signal_listener(stop_handle.clone(), grace_period)?;
+    
     match cli.command {
         Commands::Burst { 
             users, 
@@ -993,14 +1744,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             body, 
             headers, 
             content_type,
+            from_curl,
             timeout, 
             validate_url: should_validate_url,
+            workers,
+            verbose,
+            graphql,
+            stream,
+            client: mut client_options,
         } => {
-            let config = RequestConfig::from_cli(
-                url, method, body, headers, timeout, content_type
-            )?;
+            let mut config = match &from_curl {
+                Some(command) => curl_request_config(command, timeout, &mut client_options)?,
+                None => RequestConfig::from_cli(url, method, body, headers, timeout, content_type)?,
+            };
+            if let Some(graphql) = graphql.config()? {
+                config.body = graphql_body(&graphql)?;
+            }
+            config.stream = stream.config();
+            println!("🔌 Соединения: {}", client::describe(&client_options));
+            let options = RunOptions {
+                client_options,
+                workers: workers.unwrap_or(users),
+                should_validate_url,
+                dynamic_body: false,
+                verbose,
+                grace_period,
+                abort_conditions: Vec::new(),
+                stop_handle: stop_handle.clone(),
+                stop: stop.clone(),
+            };
             
-            simulate_burst(config, users, should_validate_url, false).await?;
+            simulate_burst(config, users, &options).await?;
         }
         Commands::Rps { 
             rps, 
@@ -1010,46 +1784,138 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             body, 
             headers,
             content_type,
+            from_curl,
             timeout, 
             validate_url: should_validate_url,
             dynamic_body,
+            abort_on,
+            workers,
+            verbose,
+            graphql,
+            stream,
+            client: mut client_options,
         } => {
-            let config = RequestConfig::from_cli(
-                url, method, body, headers, timeout, content_type
-            )?;
+            let mut config = match &from_curl {
+                Some(command) => curl_request_config(command, timeout, &mut client_options)?,
+                None => RequestConfig::from_cli(url, method, body, headers, timeout, content_type)?,
+            };
+            if let Some(graphql) = graphql.config()? {
+                config.body = graphql_body(&graphql)?;
+            }
+            config.stream = stream.config();
+            println!("🔌 Соединения: {}", client::describe(&client_options));
+            let options = RunOptions {
+                client_options,
+                workers: workers.unwrap_or(rps * 2),
+                should_validate_url,
+                dynamic_body,
+                verbose,
+                grace_period,
+                abort_conditions: abort_on,
+                stop_handle: stop_handle.clone(),
+                stop: stop.clone(),
+            };
+            
+            if !options.abort_conditions.is_empty() {
+                println!("🛑 Условия остановки:");
+                for condition in &options.abort_conditions {
+                    println!("  {}", condition);
+                }
+            }
             
-            simulate_rps(config, rps, duration, should_validate_url, dynamic_body).await?;
+            simulate_rps(config, rps, duration, &options).await?;
         }
         Commands::Check { 
             url, 
             method, 
             body, 
+            body_type,
             headers,
+            from_curl,
+            config_file,
+            env_file,
+            profile,
         } => {
-            check_config(url, method, body, headers)?;
+            match (from_curl, config_file) {
+                (Some(command), _) => {
+                    let curl = CurlRequest::parse(&command)?;
+                    println!("💡 Эквивалентная команда: {}", curl.check_invocation());
+                    let (body, body_kind) = curl.body_field();
+                    check_config(curl.url, curl.method, body, body_kind, curl.headers)?;
+                }
+                (None, Some(path)) => {
+                    check_config_file(&path, &config_env(env_file.as_deref())?, profile.as_deref())?;
+                }
+                (None, None) => check_config(url.unwrap_or_default(), method, body, body_type, headers)?,
+            }
         }
         Commands::Multi(multi_config) => {
-            handle_multi_command(multi_config).await?;
+            handle_multi_command(multi_config, grace_period, stop_handle.clone(), stop.clone()).await?;
+        }
+        Commands::Ws(ws_config) => {
+            ws::run(ws_config, grace_period, stop_handle.clone(), stop.clone()).await?;
+        }
+        Commands::Grpc(grpc_config) => {
+            grpc::run(grpc_config, grace_period, stop_handle.clone(), stop.clone()).await?;
+        }
+        Commands::Tcp(raw_config) => {
+            raw::run(raw::Protocol::Tcp, raw_config, grace_period, stop_handle.clone(), stop.clone()).await?;
+        }
+        Commands::Udp(raw_config) => {
+            raw::run(raw::Protocol::Udp, raw_config, grace_period, stop_handle.clone(), stop.clone()).await?;
         }
+        Commands::Replay(replay_config) => {
+            replay::run(replay_config, grace_period, stop_handle.clone(), stop.clone()).await?;
+        }
+        Commands::Import(source) => {
+            import::run(source)?;
+        }
+    }
+    
+    if let Some(reason) = stop.reason() {
+        std::process::exit(reason.exit_code());
     }
     
     Ok(())
 }
 
-async fn handle_multi_command(config: MultiUrlConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let configs = if let Some(file_path) = &config.config_file {
+async fn handle_multi_command(
+    config: MultiUrlConfig,
+    grace_period: Duration,
+    stop_handle: StopHandle,
+    stop: StopSignal,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Параметры из файла используются, если не заданы в командной строке
+    let (configs, test_config) = if let Some(file_path) = &config.config_file {
         // Загружаем из файла
-        load_configs_from_file(file_path, config.headers.clone(), config.timeout)?
+        let test_config = MultiTestConfig::load(
+            file_path,
+            &config_env(config.env_file.as_deref())?,
+            config.profile.as_deref(),
+        )?;
+        warn_unmasked_secrets();
+        (configs_from_test_config(&test_config, &config)?, test_config)
     } else if let Some(url_list) = &config.url_list {
         // Используем список URL из CLI
-        create_configs_from_urls(
+        let configs = create_configs_from_urls(
             url_list.clone(),
-            config.method,
+            config.method.clone(),
             config.body.clone(),
-            config.headers,
+            config.headers.clone(),
             config.timeout,
             config.content_type.clone(),
-        )?
+        )?;
+        let mut configs = match config.graphql.config()? {
+            Some(graphql) => {
+                let body = graphql_body(&graphql)?;
+                configs.into_iter().map(|config| RequestConfig { body: body.clone(), ..config }).collect()
+            }
+            None => configs,
+        };
+        for request_config in &mut configs {
+            request_config.stream = config.stream.config();
+        }
+        (configs, MultiTestConfig::from_urls(url_list.clone()))
     } else {
         return Err("Either --config-file or --url-list must be specified".into());
     };
@@ -1058,28 +1924,143 @@ async fn handle_multi_command(config: MultiUrlConfig) -> Result<(), Box<dyn std:
         return Err("No URLs configured for testing".into());
     }
     
+    let distribution = match (&config.distribution, &test_config.distribution) {
+        (Some(distribution), _) => distribution.clone(),
+        (None, Some(name)) => UrlDistribution::from_str(name, true)
+            .map_err(|e| format!("Некорректное распределение {}: {}", name, e))?,
+        (None, None) => UrlDistribution::RoundRobin,
+    };
+    
     // Создаем тестер
-    let tester = MultiUrlTester::new(configs, config.distribution.clone());
+    let tester = MultiUrlTester::new(configs, distribution, &config.client).await?;
     let tester_arc = Arc::new(tester);
     
-    // Для multi режима используем burst логику, но можно добавить RPS
-    // Определяем количество пользователей (можно добавить параметр)
-    let users = 20; // По умолчанию
+    let rps = config.rps.or(test_config.rps);
+    let duration = config.duration.or(test_config.duration_seconds);
+    let total_requests = config.total_requests.or(test_config.total_requests);
+    
+    let (load, default_workers) = if let Some(rps) = rps {
+        if rps == 0 {
+            return Err("RPS должен быть больше нуля".into());
+        }
+        // Без явного числа запросов тест идет duration секунд (по умолчанию 10)
+        let planned = rps * duration.unwrap_or(10) as usize;
+        let total_requests = match (total_requests, duration) {
+            (Some(total), None) => total,
+            (Some(total), Some(_)) => total.min(planned),
+            (None, _) => planned,
+        };
+        (MultiLoad::Rps { rps, total_requests }, rps * 2)
+    } else {
+        let users = config.users.or(test_config.users).unwrap_or(20);
+        // С duration без total_requests пользователи шлют запросы до истечения времени
+        let total_requests = match (total_requests, duration) {
+            (Some(total), _) => total,
+            (None, Some(_)) => usize::MAX,
+            (None, None) => users,
+        };
+        let load = MultiLoad::Burst {
+            users,
+            total_requests,
+            duration: duration.map(Duration::from_secs),
+        };
+        (load, users)
+    };
     
-    println!("🎯 ЗАПУСК МУЛЬТИ-URL ТЕСТА");
+    println!("🎯 ЗАПУСК МУЛЬТИ-URL ТЕСТА{}",
+        test_config.name.as_deref().map(|name| format!(": {}", name)).unwrap_or_default());
+    if let Some(profile) = &config.profile {
+        println!("🏷️  Профиль: {}", profile);
+    }
+    println!("🔌 Соединения: {}", client::describe(&config.client));
     println!("{}", "=".repeat(50));
     
-    let stats = simulate_multiple_urls(
-        tester_arc,
-        users,
-        config.validate_url,
-        false, // dynamic_body - можно добавить в конфиг
-    ).await?;
+    let options = RunOptions {
+        client_options: config.client.clone(),
+        workers: config.workers.unwrap_or(default_workers),
+        should_validate_url: config.validate_url,
+        dynamic_body: test_config.dynamic_body.unwrap_or(false),
+        verbose: config.verbose,
+        grace_period,
+        abort_conditions: Vec::new(),
+        stop_handle,
+        stop,
+    };
+    
+    let (stats, elapsed) = simulate_multiple_urls(tester_arc, load, &options).await?;
     
     // Выводим дополнительные метрики
     println!("\n🎯 ИТОГОВЫЕ МЕТРИКИ:");
     println!("📈 Общая пропускная способность: {:.1} запр/сек", 
-        stats.total_requests as f32 / stats.total_duration.as_secs_f32());
+        stats.total_requests as f32 / elapsed.as_secs_f32());
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn timestamp() -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 10, 10, 15, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn dynamic_json_placeholders_stay_valid_json() {
+        let body = BodyType::Json(serde_json::json!({
+            "id": "{{userId}}",
+            "ts": "{{timestamp}}",
+            "request": "{{uuid}}",
+            "label": "user-{{userId}}",
+        }));
+        let BodyType::Json(json) = prepare_dynamic_body(&body, 7, timestamp(), false) else {
+            panic!("body должен остаться JSON");
+        };
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["ts"], "2024-10-10T15:00:00+00:00");
+        assert!(uuid::Uuid::parse_str(json["request"].as_str().unwrap()).is_ok());
+        assert_eq!(json["label"], "user-7");
+    }
+
+    #[test]
+    fn dynamic_graphql_variables_stay_valid_json() {
+        let body = BodyType::GraphQl {
+            query: "query { me { id } }".to_string(),
+            operation_name: None,
+            variables: Some(serde_json::json!({"since": "{{timestamp}}"})),
+        };
+        let BodyType::GraphQl { variables, .. } = prepare_dynamic_body(&body, 1, timestamp(), false) else {
+            panic!("body должен остаться GraphQL");
+        };
+        assert_eq!(variables.unwrap()["since"], "2024-10-10T15:00:00+00:00");
+    }
+
+    #[test]
+    fn dynamic_values_are_form_encoded_in_urlencoded_text() {
+        let body = BodyType::Text("user={{userId}}&at={{timestamp}}&id={{uuid}}".to_string());
+        let BodyType::Text(sent) = prepare_dynamic_body(&body, 3, timestamp(), true) else {
+            panic!("body формы должен остаться текстом");
+        };
+        assert!(sent.starts_with("user=3&at=2024-10-10T15%3A00%3A00%2B00%3A00&id="), "{}", sent);
+        let fields: HashMap<String, String> = url::form_urlencoded::parse(sent.as_bytes()).into_owned().collect();
+        assert_eq!(fields["at"], "2024-10-10T15:00:00+00:00");
+        assert!(uuid::Uuid::parse_str(&fields["id"]).is_ok());
+
+        // Без form_urlencoded текст получает значения как есть
+        let BodyType::Text(sent) = prepare_dynamic_body(&body, 3, timestamp(), false) else {
+            panic!("body должен остаться текстом");
+        };
+        assert!(sent.starts_with("user=3&at=2024-10-10T15:00:00+00:00&id="), "{}", sent);
+    }
+
+    #[test]
+    fn dynamic_values_are_substituted_in_binary_body() {
+        let body = BodyType::Binary(b"\x00name=\"user\"\r\n\r\n{{userId}}-{{timestamp}}\r\n{{userId}}\xff".to_vec());
+        let BodyType::Binary(sent) = prepare_dynamic_body(&body, 12, timestamp(), false) else {
+            panic!("body должен остаться двоичным");
+        };
+        assert_eq!(sent, b"\x00name=\"user\"\r\n\r\n12-2024-10-10T15:00:00+00:00\r\n12\xff");
+    }
+}
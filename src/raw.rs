@@ -0,0 +1,479 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use load_test::AbortCondition;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::guardrails::Guardrails;
+use crate::shutdown::{StopHandle, StopSignal};
+use crate::stats::{spawn_aggregator, ErrorClass, RequestResult};
+use crate::{
+    join_workers, load_jobs, parse_body, print_stop_notice, BodyType, JobSource, FAILURE_SAMPLES,
+};
+
+// Максимальный размер датаграммы UDP
+const MAX_DATAGRAM: usize = 65_507;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Protocol {
+    Tcp,
+    Udp,
+}
+
+#[derive(Args)]
+pub(crate) struct RawConfig {
+    /// Адрес сервера в формате host:port
+    #[arg(short = 'U', long, default_value = "127.0.0.1:9000")]
+    target: String,
+
+    /// Данные: base64, hex:0a0b..., text:строка, @путь к файлу; не base64 отправляется как текст
+    #[arg(short = 'd', long)]
+    data: String,
+
+    /// Ждать ответ до разделителя (\n, \r, \t, \0 или hex:...), только TCP
+    #[arg(long, conflicts_with = "reply_length_prefix")]
+    reply_delimiter: Option<String>,
+
+    /// Ждать ответ с префиксом длины в 1, 2 или 4 байта (big-endian), только TCP
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+    reply_length_prefix: Option<u8>,
+
+    /// Ждать любой ответ: первые полученные байты (TCP) или одну датаграмму (UDP)
+    #[arg(long, conflicts_with_all = ["reply_delimiter", "reply_length_prefix"])]
+    wait_reply: bool,
+
+    /// Количество соединений (сокетов), каждое отправляет запросы по очереди
+    #[arg(short, long, default_value_t = 10)]
+    concurrency: usize,
+
+    /// Всего запросов (по умолчанию равно --concurrency, с --duration - без ограничения)
+    #[arg(short = 'n', long)]
+    total_requests: Option<usize>,
+
+    /// Запросов в секунду (без ограничения, если не задано)
+    #[arg(short, long)]
+    rps: Option<usize>,
+
+    /// Длительность теста в секундах (с --rps по умолчанию 10)
+    #[arg(long)]
+    duration: Option<u64>,
+
+    /// Новое TCP-соединение на каждый запрос
+    #[arg(long)]
+    reconnect: bool,
+
+    /// Таймаут подключения и ожидания ответа в секундах
+    #[arg(short, long, default_value_t = 10)]
+    timeout: u64,
+
+    /// Остановить тест по условию, например "error_rate>5%" или "p99>800ms for 10s"
+    #[arg(long = "abort-on")]
+    abort_on: Vec<AbortCondition>,
+
+    /// Печатать строку на каждый запрос
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+// Как определить конец ответа
+#[derive(Debug)]
+enum Reply {
+    None,
+    Any,
+    Delimiter(Vec<u8>),
+    LengthPrefix(usize),
+}
+
+// Общие данные всех воркеров
+struct RawContext {
+    address: SocketAddr,
+    url: Arc<str>,
+    payload: Vec<u8>,
+    reply: Reply,
+    timeout: Duration,
+    reconnect: bool,
+    jobs: JobSource,
+    verbose: bool,
+    stop: StopSignal,
+    sent: AtomicUsize,
+    received: AtomicUsize,
+}
+
+// Открытое TCP-соединение и байты ответа, прочитанные сверх предыдущего ответа
+struct Connection {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+}
+
+pub(crate) async fn run(
+    protocol: Protocol,
+    config: RawConfig,
+    grace_period: Duration,
+    stop_handle: StopHandle,
+    stop: StopSignal,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if config.concurrency == 0 {
+        return Err("Количество соединений должно быть больше нуля".into());
+    }
+
+    let payload = parse_payload(&config.data)?;
+    let reply = match (&config.reply_delimiter, config.reply_length_prefix) {
+        (Some(_), _) | (_, Some(_)) if protocol == Protocol::Udp => {
+            return Err("Для UDP ответ определяется датаграммой, используйте --wait-reply".into());
+        }
+        (Some(delimiter), _) => Reply::Delimiter(parse_delimiter(delimiter)?),
+        (None, Some(3)) => return Err("Префикс длины может быть 1, 2 или 4 байта".into()),
+        (None, Some(bytes)) => Reply::LengthPrefix(bytes as usize),
+        (None, None) if config.wait_reply => Reply::Any,
+        (None, None) => Reply::None,
+    };
+    if protocol == Protocol::Udp && payload.len() > MAX_DATAGRAM {
+        return Err(format!("Датаграмма UDP не больше {} байт, данные: {}", MAX_DATAGRAM, payload.len()).into());
+    }
+
+    let address = tokio::net::lookup_host(&config.target).await
+        .map_err(|e| format!("Не удалось разрешить {}: {}", config.target, e))?
+        .next()
+        .ok_or_else(|| format!("Нет адресов для {}", config.target))?;
+
+    let (jobs, scheduler) = load_jobs(
+        config.rps, config.total_requests, config.duration, config.concurrency, &stop,
+    )?;
+
+    let scheme = match protocol {
+        Protocol::Tcp => "tcp",
+        Protocol::Udp => "udp",
+    };
+    println!("🧱 {}: {} ({})", scheme.to_uppercase(), config.target, address);
+    match config.rps {
+        Some(rps) => println!("📈 {} запросов в секунду", rps),
+        None => println!("🚀 Запросы без паузы"),
+    }
+    println!("🔌 Соединений: {}{}", config.concurrency,
+        if config.reconnect && protocol == Protocol::Tcp { " (новое на каждый запрос)" } else { "" });
+    if let Some(total) = config.total_requests {
+        println!("🔢 Всего запросов: {}", total);
+    }
+    if let Some(duration) = config.duration {
+        println!("⏳ Длительность: {} секунд", duration);
+    }
+    println!("📦 Данные: {} байт", payload.len());
+    match &reply {
+        Reply::None => println!("📨 Ответ: не ждать"),
+        Reply::Any => println!("📨 Ответ: любые данные"),
+        Reply::Delimiter(delimiter) => println!("📨 Ответ: до разделителя {}", hex::encode(delimiter)),
+        Reply::LengthPrefix(bytes) => println!("📨 Ответ: с префиксом длины {} байт", bytes),
+    }
+    println!("⏱️  Таймаут: {} секунд", config.timeout);
+    if !config.abort_on.is_empty() {
+        println!("🛑 Условия остановки:");
+        for condition in &config.abort_on {
+            println!("  {}", condition);
+        }
+    }
+    println!("{}", "=".repeat(50));
+
+    let context = Arc::new(RawContext {
+        address,
+        url: Arc::from(format!("{}://{}", scheme, config.target)),
+        payload,
+        reply,
+        timeout: Duration::from_secs(config.timeout),
+        reconnect: config.reconnect,
+        jobs,
+        verbose: config.verbose,
+        stop: stop.clone(),
+        sent: AtomicUsize::new(0),
+        received: AtomicUsize::new(0),
+    });
+
+    let (results_tx, results_rx) = mpsc::channel(config.concurrency * 2);
+    let guardrails = Guardrails::new(config.abort_on, stop_handle);
+    let aggregator = spawn_aggregator(results_rx, FAILURE_SAMPLES, config.rps.is_some(), guardrails);
+    // Подключения TCP учитываются отдельно от запросов
+    let (connects_tx, connects_rx) = mpsc::channel(config.concurrency * 2);
+    let connects = spawn_aggregator(connects_rx, FAILURE_SAMPLES, false, None);
+
+    let test_start = Instant::now();
+    let handles: Vec<JoinHandle<()>> = (0..config.concurrency)
+        .map(|_| {
+            let context = context.clone();
+            let results = results_tx.clone();
+            match protocol {
+                Protocol::Tcp => tokio::spawn(tcp_worker(context, results, connects_tx.clone())),
+                Protocol::Udp => tokio::spawn(udp_worker(context, results)),
+            }
+        })
+        .collect();
+    drop(results_tx);
+    drop(connects_tx);
+
+    join_workers(handles, &stop, grace_period).await;
+    if let Some(scheduler) = scheduler
+        && let Err(e) = scheduler.await
+    {
+        eprintln!("Ошибка в задаче: {}", e);
+    }
+    let report = aggregator.await?;
+    let connects = connects.await?;
+
+    println!("\n{}", "=".repeat(50));
+    println!("🎯 ИТОГИ ТЕСТА:");
+    print_stop_notice(&stop);
+
+    if protocol == Protocol::Tcp {
+        println!("\n🔌 ПОДКЛЮЧЕНИЯ (время установки соединения):");
+        connects.stats.print_summary();
+    }
+    println!("\n📨 ЗАПРОСЫ (время до ответа):");
+    report.stats.print_summary();
+
+    let total_duration = test_start.elapsed();
+    println!("\n📦 Отправлено: {} байт, получено: {} байт",
+        context.sent.load(Ordering::Relaxed), context.received.load(Ordering::Relaxed));
+    println!("⏰ Общее время теста: {:.2} секунд", total_duration.as_secs_f32());
+    let actual_rps = report.stats.total_requests as f32 / total_duration.as_secs_f32();
+    println!("📊 Фактически запросов в секунду: {:.1}", actual_rps);
+    // Каждое соединение ждет ответа перед следующим запросом, поэтому -c ограничивает частоту;
+    // в коротком тесте частоту занижает ожидание последних ответов, там предупреждения нет
+    if let Some(rps) = config.rps
+        && !stop.is_stopped()
+        && total_duration >= Duration::from_secs(1)
+        && actual_rps < rps as f32 * 0.8
+    {
+        println!("⚠️  Частота ниже заданной ({}): {} соединений не успевают, увеличьте -c",
+            rps, config.concurrency);
+    }
+
+    if connects.stats.failed > 0 {
+        println!("\n🔍 Неудачные подключения (выборка, до {}):", FAILURE_SAMPLES);
+        for failure in connects.failures.iter() {
+            println!("  Запрос {}: {}", failure.user_id, failure.error.as_deref().unwrap_or("Unknown"));
+        }
+    }
+    report.print_failures();
+    Ok(())
+}
+
+// Данные в формате тела HTTP-запроса (base64 декодируется как BodyType::Binary),
+// а также hex:..., text:... и @файл
+fn parse_payload(data: &str) -> Result<Vec<u8>, String> {
+    if let Some(text) = data.strip_prefix("text:") {
+        return Ok(text.as_bytes().to_vec());
+    }
+    if let Some(path) = data.strip_prefix('@') {
+        return std::fs::read(path).map_err(|e| format!("Не удалось прочитать {}: {}", path, e));
+    }
+    if let Some(encoded) = data.strip_prefix("hex:") {
+        return hex::decode(encoded.replace(' ', "")).map_err(|e| format!("Некорректные hex-данные: {}", e));
+    }
+    match parse_body(data)? {
+        BodyType::Binary(bytes) => Ok(bytes),
+        _ => Ok(data.as_bytes().to_vec()),
+    }
+}
+
+fn parse_delimiter(delimiter: &str) -> Result<Vec<u8>, String> {
+    if let Some(encoded) = delimiter.strip_prefix("hex:") {
+        return hex::decode(encoded).map_err(|e| format!("Некорректный разделитель: {}", e));
+    }
+    let unescaped = delimiter
+        .replace("\\n", "\n")
+        .replace("\\r", "\r")
+        .replace("\\t", "\t")
+        .replace("\\0", "\0");
+    if unescaped.is_empty() {
+        return Err("Разделитель ответа не может быть пустым".to_string());
+    }
+    Ok(unescaped.into_bytes())
+}
+
+async fn tcp_worker(
+    context: Arc<RawContext>,
+    results: mpsc::Sender<RequestResult>,
+    connects: mpsc::Sender<RequestResult>,
+) {
+    let mut connection: Option<Connection> = None;
+
+    while let Some(user_id) = context.jobs.next(&context.stop).await {
+        let start = Instant::now();
+        if connection.is_none() {
+            let outcome = match tokio::time::timeout(context.timeout, TcpStream::connect(context.address)).await {
+                Ok(Ok(stream)) => {
+                    let _ = stream.set_nodelay(true);
+                    connection = Some(Connection { stream, buffer: Vec::new() });
+                    Ok(0)
+                }
+                Ok(Err(e)) => Err((ErrorClass::Connect, format!("Ошибка подключения: {}", e))),
+                Err(_) => Err((ErrorClass::Timeout, "Таймаут подключения".to_string())),
+            };
+            let connect_failed = outcome.is_err();
+            let connect = result(&context, user_id, start.elapsed(), outcome);
+            if connect_failed {
+                // Запрос без соединения тоже считается неудачным
+                let request = request_result(&context, user_id, connect.duration, Err((
+                    connect.error_class.unwrap_or(ErrorClass::Connect),
+                    connect.error.clone().unwrap_or_default(),
+                )));
+                if connects.send(connect).await.is_err() || results.send(request).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            if connects.send(connect).await.is_err() {
+                break;
+            }
+        }
+
+        let Some(current) = connection.as_mut() else { continue };
+        let start = Instant::now();
+        let outcome = match tokio::time::timeout(context.timeout, tcp_round_trip(&context, current)).await {
+            Ok(outcome) => outcome,
+            Err(_) => Err((ErrorClass::Timeout, "Нет ответа".to_string())),
+        };
+        // После ошибки состояние потока неизвестно, следующий запрос откроет новое соединение
+        if outcome.is_err() || context.reconnect {
+            connection = None;
+        }
+
+        let result = request_result(&context, user_id, start.elapsed(), outcome);
+        if results.send(result).await.is_err() {
+            break;
+        }
+    }
+}
+
+// Отправляет данные и читает ответ; возвращает размер ответа
+async fn tcp_round_trip(context: &RawContext, connection: &mut Connection) -> Result<usize, (ErrorClass, String)> {
+    let stream = &mut connection.stream;
+    let buffer = &mut connection.buffer;
+    stream.write_all(&context.payload).await
+        .map_err(|e| (ErrorClass::Connect, format!("Ошибка отправки: {}", e)))?;
+    context.sent.fetch_add(context.payload.len(), Ordering::Relaxed);
+
+    let needed = |buffer: &[u8]| -> Option<usize> {
+        match &context.reply {
+            Reply::None => Some(0),
+            Reply::Any => (!buffer.is_empty()).then_some(buffer.len()),
+            Reply::Delimiter(delimiter) => buffer
+                .windows(delimiter.len())
+                .position(|window| window == delimiter.as_slice())
+                .map(|position| position + delimiter.len()),
+            Reply::LengthPrefix(bytes) => {
+                let prefix = buffer.get(..*bytes)?;
+                let length = prefix.iter().fold(0usize, |length, byte| length << 8 | *byte as usize);
+                (buffer.len() >= bytes + length).then_some(bytes + length)
+            }
+        }
+    };
+
+    let mut chunk = [0u8; 8192];
+    loop {
+        if let Some(length) = needed(buffer) {
+            buffer.drain(..length);
+            context.received.fetch_add(length, Ordering::Relaxed);
+            return Ok(length);
+        }
+        match stream.read(&mut chunk).await {
+            Ok(0) => return Err((ErrorClass::Connect, "Соединение закрыто до ответа".to_string())),
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            Err(e) => return Err((ErrorClass::Connect, format!("Ошибка чтения: {}", e))),
+        }
+    }
+}
+
+async fn udp_worker(context: Arc<RawContext>, results: mpsc::Sender<RequestResult>) {
+    let local: SocketAddr = if context.address.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = match UdpSocket::bind(local).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            eprintln!("Не удалось открыть сокет UDP: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = socket.connect(context.address).await {
+        eprintln!("Не удалось подключить сокет UDP к {}: {}", context.address, e);
+        return;
+    }
+
+    let mut buffer = vec![0u8; MAX_DATAGRAM];
+    while let Some(user_id) = context.jobs.next(&context.stop).await {
+        // Опоздавшие ответы на прошлые запросы не должны засчитываться текущему
+        while socket.try_recv(&mut buffer).is_ok() {}
+
+        let start = Instant::now();
+        let outcome = async {
+            socket.send(&context.payload).await
+                .map_err(|e| (ErrorClass::Connect, format!("Ошибка отправки: {}", e)))?;
+            context.sent.fetch_add(context.payload.len(), Ordering::Relaxed);
+            if matches!(context.reply, Reply::None) {
+                return Ok(0);
+            }
+            match tokio::time::timeout(context.timeout, socket.recv(&mut buffer)).await {
+                Ok(Ok(received)) => {
+                    context.received.fetch_add(received, Ordering::Relaxed);
+                    Ok(received)
+                }
+                Ok(Err(e)) => Err((ErrorClass::Connect, format!("Ошибка приема: {}", e))),
+                Err(_) => Err((ErrorClass::Timeout, "Нет ответа".to_string())),
+            }
+        }.await;
+
+        let result = request_result(&context, user_id, start.elapsed(), outcome);
+        if results.send(result).await.is_err() {
+            break;
+        }
+    }
+}
+
+// Результат запроса; с --verbose печатается строка
+fn request_result(
+    context: &RawContext,
+    user_id: usize,
+    duration: Duration,
+    outcome: Result<usize, (ErrorClass, String)>,
+) -> RequestResult {
+    if context.verbose {
+        match &outcome {
+            Ok(received) => println!("👤 {} {} ✅ {:.2}ms, ответ {} байт",
+                user_id, context.url, duration.as_millis(), received),
+            Err((_, error)) => println!("👤 {} {} ❌ {} {:.2}ms",
+                user_id, context.url, error, duration.as_millis()),
+        }
+    }
+    result(context, user_id, duration, outcome)
+}
+
+fn result(
+    context: &RawContext,
+    user_id: usize,
+    duration: Duration,
+    outcome: Result<usize, (ErrorClass, String)>,
+) -> RequestResult {
+    let (success, error_class, error) = match outcome {
+        Ok(_) => (true, None, None),
+        Err((class, error)) => (false, Some(class), Some(error)),
+    };
+    RequestResult {
+        user_id,
+        success,
+        duration,
+        status_code: None,
+        error,
+        error_class,
+        url: context.url.clone(),
+        redirects: 0,
+        final_url: None,
+        stream: None,
+    }
+}
@@ -0,0 +1,718 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use clap::{Args, ValueEnum};
+use load_test::{AbortCondition, ClientOptions, HttpMethod};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use url::Url;
+
+use crate::auth::{AuthProviders, Authorization};
+use crate::client::{self, ClientSet};
+use crate::guardrails::Guardrails;
+use crate::import::keep_header;
+use crate::shutdown::{StopHandle, StopSignal};
+use crate::stats::{spawn_aggregator, ErrorClass, LatencyHistogram, RequestResult};
+use crate::{
+    join_workers, make_request, print_stop_notice, validate_url, BodyType, RequestConfig, RequestTemplate,
+    FAILURE_SAMPLES,
+};
+
+// Формат времени в combined-журналах nginx и Apache: 10/Oct/2024:13:55:36 +0000
+const COMBINED_TIME_FORMAT: &str = "%d/%b/%Y:%H:%M:%S %z";
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum LogFormat {
+    /// По первой строке: { - JSON lines, иначе combined
+    Auto,
+    /// Combined или common log format (nginx, Apache)
+    Combined,
+    /// JSON-объект на каждой строке
+    Jsonl,
+}
+
+#[derive(Args)]
+pub(crate) struct ReplayConfig {
+    /// Файл журнала
+    log: String,
+
+    /// Формат журнала
+    #[arg(long, value_enum, default_value = "auto")]
+    format: LogFormat,
+
+    /// Базовый URL стенда: схема и хост из журнала заменяются на него, путь и query сохраняются
+    #[arg(short = 'U', long)]
+    target: String,
+
+    /// Ускорение относительно исходного времени: 2 - вдвое быстрее, 0.5 - вдвое медленнее
+    #[arg(long, default_value_t = 1.0)]
+    speed: f64,
+
+    /// Не соблюдать исходное время, а отправлять запросы с этой частотой
+    #[arg(short, long, conflicts_with = "speed")]
+    rps: Option<usize>,
+
+    /// Воспроизвести только первые N записей
+    #[arg(short = 'n', long)]
+    limit: Option<usize>,
+
+    /// Только запросы с этими методами, например get,head
+    #[arg(long, value_enum, value_delimiter = ',')]
+    methods: Vec<HttpMethod>,
+
+    /// Дополнительные заголовки "Header: Value" для всех запросов (можно указать несколько)
+    #[arg(short = 'H', long)]
+    headers: Vec<String>,
+
+    /// Таймаут запроса в секундах
+    #[arg(short = 't', long, default_value_t = 30)]
+    timeout: u64,
+
+    /// Размер пула воркеров: сколько запросов может быть в полете одновременно
+    #[arg(short = 'w', long, default_value_t = 100)]
+    workers: usize,
+
+    /// Условие досрочной остановки, например "error_rate>20% for 10s" (можно указать несколько)
+    #[arg(long = "abort-on")]
+    abort_on: Vec<AbortCondition>,
+
+    /// Печатать строку на каждый запрос
+    #[arg(short = 'v', long)]
+    verbose: bool,
+
+    #[command(flatten)]
+    client: ClientOptions,
+}
+
+// Запрос из журнала
+struct LogEntry {
+    time: DateTime<FixedOffset>,
+    method: HttpMethod,
+    target: String,
+    headers: Vec<String>,
+    body: Option<String>,
+}
+
+// Запись журнала по порядку воспроизведения
+struct ReplayJob {
+    // Номер записи, с 1
+    index: usize,
+    // Смещение от первой записи в исходном журнале
+    offset: Duration,
+    entry: LogEntry,
+}
+
+// Итоги чтения журнала: известны только после того, как прочитан весь файл
+#[derive(Default)]
+struct LogSummary {
+    total: usize,
+    skipped: usize,
+    span: Duration,
+}
+
+// Отклонение момента отправки от расписания: позже и раньше - отдельные гистограммы
+#[derive(Default)]
+struct Drift {
+    late: LatencyHistogram,
+    early: LatencyHistogram,
+    max_late: Duration,
+}
+
+struct ReplayContext {
+    client: ClientSet,
+    client_options: ClientOptions,
+    // Базовый URL стенда без / в конце
+    target: String,
+    headers: Vec<String>,
+    timeout: u64,
+    auth: Option<Authorization>,
+    jobs: tokio::sync::Mutex<mpsc::Receiver<ReplayJob>>,
+    // Ключи статистики: путь без query, не больше MAX_STATS_KEYS разных
+    stats_keys: Mutex<HashSet<Arc<str>>>,
+    start: Instant,
+    // С --rps расписание - исходное время, с --speed - ускоренное
+    speed: f64,
+    drift: Mutex<Drift>,
+    verbose: bool,
+    stop: StopSignal,
+}
+
+// Сколько записей журнала держать впереди планировщика; журнал не загружается целиком
+const READ_AHEAD: usize = 1024;
+// Окно упорядочивания: nginx пишет запрос по завершении, поэтому соседние строки
+// бывают не по времени; записи, опоздавшие больше чем на окно, уходят сразу
+const REORDER_WINDOW: usize = 1024;
+// Сколько разных путей считать в отчете отдельно; остальные - одной строкой
+const MAX_STATS_KEYS: usize = 1000;
+const OTHER_PATHS: &str = "(прочие пути)";
+
+pub(crate) async fn run(
+    config: ReplayConfig,
+    grace_period: Duration,
+    stop_handle: StopHandle,
+    stop: StopSignal,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if config.speed.is_nan() || config.speed <= 0.0 {
+        return Err("--speed должен быть больше нуля".into());
+    }
+    if config.rps == Some(0) {
+        return Err("RPS должен быть больше нуля".into());
+    }
+    validate_url(&config.target)?;
+    let target = config.target.trim_end_matches('/').to_string();
+    let file = File::open(&config.log)
+        .map_err(|e| format!("Не удалось прочитать {}: {}", config.log, e))?;
+
+    println!("🔁 Воспроизведение журнала: {}", config.log);
+    println!("🔗 Стенд: {}", target);
+    match config.rps {
+        Some(rps) => println!("📈 Без исходного времени, {} запросов в секунду", rps),
+        None => println!("⏱️  Исходное время, ускорение x{}", config.speed),
+    }
+    println!("🔌 Соединения: {}", client::describe(&config.client));
+    println!("👷 Воркеров: {}", config.workers);
+    if !config.abort_on.is_empty() {
+        println!("🛑 Условия остановки:");
+        for condition in &config.abort_on {
+            println!("  {}", condition);
+        }
+    }
+    println!("{}", "=".repeat(50));
+
+    // Все записи идут на один стенд, поэтому клиент с подменой имени сервера (--tls-server-name)
+    // строится один раз, а шаблонам запросов эта настройка уже не передается
+    let mut request_options = config.client.clone();
+    let (client, target) = if client::needs_own_client(&config.client, None) {
+        let mut url = Url::parse(&target).map_err(|e| format!("Некорректный URL: {}", e))?;
+        let client = client::build_client_for_url(&config.client, None, &mut url).await?;
+        request_options.tls.tls_server_name = None;
+        (client, url.as_str().trim_end_matches('/').to_string())
+    } else {
+        (client::build_client(&config.client)?, target)
+    };
+    let auth = match config.client.auth.config() {
+        Some(auth) => Some(AuthProviders::new(client.for_worker(0).clone()).get(&auth).await?),
+        None => None,
+    };
+
+    // Журнал читается в отдельном потоке и передается дальше через ограниченные очереди
+    let (entries_tx, entries_rx) = mpsc::channel(READ_AHEAD);
+    let reader = {
+        let (format, methods, limit) = (config.format, config.methods.clone(), config.limit);
+        tokio::task::spawn_blocking(move || read_log(BufReader::new(file), format, &methods, limit, entries_tx))
+    };
+    // Отклонение считается от того же момента, от которого идет расписание
+    let start = Instant::now();
+    let (jobs, scheduler) = spawn_replay_scheduler(start, entries_rx, config.speed, config.rps, stop.clone());
+
+    let context = Arc::new(ReplayContext {
+        client,
+        client_options: request_options,
+        target,
+        headers: config.headers.clone(),
+        timeout: config.timeout,
+        auth,
+        jobs: tokio::sync::Mutex::new(jobs),
+        stats_keys: Mutex::new(HashSet::new()),
+        start,
+        speed: if config.rps.is_some() { 1.0 } else { config.speed },
+        drift: Mutex::new(Drift::default()),
+        verbose: config.verbose,
+        stop: stop.clone(),
+    });
+
+    let workers = config.workers.max(1);
+    let (results_tx, results_rx) = mpsc::channel(workers * 2);
+    let guardrails = Guardrails::new(config.abort_on, stop_handle);
+    let aggregator = spawn_aggregator(results_rx, FAILURE_SAMPLES, true, guardrails);
+
+    let handles: Vec<JoinHandle<()>> = (0..workers)
+        .map(|worker| {
+            let context = context.clone();
+            let results = results_tx.clone();
+            tokio::spawn(async move {
+                while let Some(job) = context.next_job().await {
+                    context.record_drift(job.offset);
+                    let result = context.send(worker, job).await;
+                    if results.send(result).await.is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(results_tx);
+
+    join_workers(handles, &stop, grace_period).await;
+    // Планировщик и поток чтения видят закрытые очереди и завершаются
+    context.jobs.lock().await.close();
+    if let Err(e) = scheduler.await {
+        eprintln!("Ошибка в задаче: {}", e);
+    }
+    let summary = reader.await??;
+    let report = aggregator.await?;
+    let elapsed = context.start.elapsed();
+    if summary.total == 0 {
+        return Err(format!("В {} нет запросов для воспроизведения", config.log).into());
+    }
+
+    println!("\n{}", "=".repeat(50));
+    println!("🎯 ИТОГИ ВОСПРОИЗВЕДЕНИЯ:");
+    print_stop_notice(&stop);
+    println!("📜 Записей журнала: {} (пропущено строк: {})", summary.total, summary.skipped);
+    report.stats.print_summary();
+    report.print_streams(elapsed);
+
+    println!("\n🕰️  Отклонение от исходного времени:");
+    let drift = context.drift.lock().unwrap_or_else(|e| e.into_inner());
+    print_drift("Позже", &drift.late, Some(drift.max_late));
+    print_drift("Раньше", &drift.early, None);
+    println!("  Исходная длительность: {:.2} секунд{}, воспроизведение: {:.2} секунд",
+        summary.span.as_secs_f64(),
+        match config.rps {
+            Some(_) => String::new(),
+            None => format!(" (x{}: {:.2})", config.speed, summary.span.as_secs_f64() / config.speed),
+        },
+        elapsed.as_secs_f64());
+
+    report.print_failures();
+    Ok(())
+}
+
+impl ReplayContext {
+    async fn next_job(&self) -> Option<ReplayJob> {
+        if self.stop.is_stopped() {
+            return None;
+        }
+        let mut jobs = self.jobs.lock().await;
+        tokio::select! {
+            job = jobs.recv() => job,
+            _ = self.stop.stopped() => None,
+        }
+    }
+
+    fn record_drift(&self, offset: Duration) {
+        let actual = self.start.elapsed();
+        let expected = offset.div_f64(self.speed);
+        let mut drift = self.drift.lock().unwrap_or_else(|e| e.into_inner());
+        if actual >= expected {
+            let late = actual - expected;
+            drift.late.record(late);
+            drift.max_late = drift.max_late.max(late);
+        } else {
+            drift.early.record(expected - actual);
+        }
+    }
+
+    // Шаблон собирается на каждую запись: в памяти только запросы в полете
+    async fn send(&self, worker: usize, job: ReplayJob) -> RequestResult {
+        let ReplayJob { index, entry, .. } = job;
+        let key = self.stats_key(&entry);
+        let mut headers = entry.headers;
+        headers.extend(self.headers.iter().cloned());
+        let request_config = RequestConfig::from_cli(
+            format!("{}{}", self.target, entry.target),
+            entry.method,
+            None,
+            headers,
+            self.timeout,
+            None,
+        )
+        .map(|mut request_config| {
+            // Body из журнала отправляется тем же текстом, без угадывания типа
+            if let Some(body) = entry.body {
+                request_config.body = BodyType::Text(body);
+            }
+            request_config
+        });
+        let template = match request_config {
+            Ok(request_config) => RequestTemplate::new(request_config, &self.client_options).await,
+            Err(error) => Err(error),
+        };
+
+        let mut template = match template {
+            Ok(template) => template,
+            Err(error) => {
+                return RequestResult {
+                    user_id: index,
+                    success: false,
+                    duration: Duration::ZERO,
+                    status_code: None,
+                    error: Some(error),
+                    error_class: Some(ErrorClass::Other),
+                    url: key,
+                    redirects: 0,
+                    final_url: None,
+                    stream: None,
+                };
+            }
+        };
+        template.url_str = key;
+        template.auth = self.auth.clone();
+        let client = template.client.as_ref().unwrap_or(&self.client).for_worker(worker);
+        make_request(client, &template, index, false, self.verbose).await
+    }
+
+    // Метод и путь без query; после MAX_STATS_KEYS разных путей новые идут в общую строку
+    fn stats_key(&self, entry: &LogEntry) -> Arc<str> {
+        let path = entry.target.split('?').next().unwrap_or_default();
+        let key = format!("{:?} {}{}", entry.method, self.target, path);
+        let mut keys = self.stats_keys.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(key) = keys.get(key.as_str()) {
+            return key.clone();
+        }
+        if keys.len() >= MAX_STATS_KEYS {
+            return Arc::from(OTHER_PATHS);
+        }
+        let key: Arc<str> = Arc::from(key);
+        keys.insert(key.clone());
+        key
+    }
+}
+
+fn print_drift(name: &str, histogram: &LatencyHistogram, max: Option<Duration>) {
+    if histogram.is_empty() {
+        return;
+    }
+    let max = max.map(|max| format!(", max {}ms", max.as_millis())).unwrap_or_default();
+    println!("  {} расписания: {} запросов, p50 {}ms, p90 {}ms, p99 {}ms{}",
+        name,
+        histogram.total(),
+        histogram.percentile(50.0).as_millis(),
+        histogram.percentile(90.0).as_millis(),
+        histogram.percentile(99.0).as_millis(),
+        max);
+}
+
+// Выдает записи воркерам: с --rps - с заданной частотой, иначе в момент offset / speed от запуска
+fn spawn_replay_scheduler(
+    start: Instant,
+    mut entries: mpsc::Receiver<ReplayJob>,
+    speed: f64,
+    rps: Option<usize>,
+    stop: StopSignal,
+) -> (mpsc::Receiver<ReplayJob>, JoinHandle<()>) {
+    let (jobs_tx, jobs_rx) = mpsc::channel(READ_AHEAD);
+
+    let scheduler = tokio::spawn(async move {
+        let start = tokio::time::Instant::from_std(start);
+        let schedule = async {
+            let mut ticker = rps.map(|rps| {
+                tokio::time::interval((Duration::from_secs(1) / rps as u32).max(Duration::from_nanos(1)))
+            });
+            while let Some(job) = entries.recv().await {
+                match &mut ticker {
+                    Some(ticker) => { ticker.tick().await; }
+                    None => tokio::time::sleep_until(start + job.offset.div_f64(speed)).await,
+                }
+                if jobs_tx.send(job).await.is_err() {
+                    break;
+                }
+            }
+        };
+
+        tokio::select! {
+            _ = schedule => {}
+            _ = stop.stopped() => {}
+        }
+    });
+
+    (jobs_rx, scheduler)
+}
+
+// Читает журнал построчно и отправляет записи по времени в очередь.
+// Останавливается, когда очередь закрыта: тест завершен досрочно.
+fn read_log(
+    reader: impl BufRead,
+    format: LogFormat,
+    methods: &[HttpMethod],
+    limit: Option<usize>,
+    entries: mpsc::Sender<ReplayJob>,
+) -> Result<LogSummary, String> {
+    let mut summary = LogSummary::default();
+    let mut format = format;
+    let mut pending: BinaryHeap<Reverse<Pending>> = BinaryHeap::new();
+    let mut first: Option<DateTime<FixedOffset>> = None;
+    let limit = limit.unwrap_or(usize::MAX);
+
+    // Отправляет самую раннюю из ожидающих записей; false - очередь закрыта или лимит исчерпан
+    let mut emit = |pending: &mut BinaryHeap<Reverse<Pending>>, summary: &mut LogSummary| -> bool {
+        let Some(Reverse(Pending { entry, .. })) = pending.pop() else { return true };
+        if summary.total >= limit {
+            return false;
+        }
+        let first = *first.get_or_insert(entry.time);
+        let offset = (entry.time - first).to_std().unwrap_or_default();
+        summary.total += 1;
+        summary.span = summary.span.max(offset);
+        entries.blocking_send(ReplayJob { index: summary.total, offset, entry }).is_ok()
+    };
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("Ошибка чтения журнала: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let LogFormat::Auto = format {
+            format = if line.trim_start().starts_with('{') { LogFormat::Jsonl } else { LogFormat::Combined };
+        }
+        let entry = match format {
+            LogFormat::Jsonl => parse_json_line(&line),
+            _ => parse_combined(&line),
+        };
+        match entry {
+            Some(entry) if methods.is_empty() || methods.contains(&entry.method) => {
+                pending.push(Reverse(Pending { time: entry.time, line: line_number, entry }));
+            }
+            _ => summary.skipped += 1,
+        }
+        if pending.len() > REORDER_WINDOW && !emit(&mut pending, &mut summary) {
+            return Ok(summary);
+        }
+    }
+    while !pending.is_empty() {
+        if !emit(&mut pending, &mut summary) {
+            break;
+        }
+    }
+    Ok(summary)
+}
+
+// Запись в окне упорядочивания: по времени, при равном времени - по номеру строки
+struct Pending {
+    time: DateTime<FixedOffset>,
+    line: usize,
+    entry: LogEntry,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        (self.time, self.line) == (other.time, other.line)
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.time, self.line).cmp(&(other.time, other.line))
+    }
+}
+
+// 127.0.0.1 - - [10/Oct/2024:13:55:36 +0000] "GET /api?x=1 HTTP/1.1" 200 512 "referer" "user-agent"
+fn parse_combined(line: &str) -> Option<LogEntry> {
+    let open = line.find('[')?;
+    let close = open + line[open..].find(']')?;
+    let time = DateTime::parse_from_str(&line[open + 1..close], COMBINED_TIME_FORMAT).ok()?;
+
+    let quoted = quoted_fields(&line[close + 1..]);
+    let mut request = quoted.first()?.split_whitespace();
+    let method = HttpMethod::from_str(request.next()?, true).ok()?;
+    let target = request_target(request.next()?)?;
+
+    let mut headers = Vec::new();
+    for (index, name) in [(1, "Referer"), (2, "User-Agent")] {
+        if let Some(value) = quoted.get(index).filter(|value| !value.is_empty() && *value != "-") {
+            headers.push(format!("{}: {}", name, value));
+        }
+    }
+    Some(LogEntry { time, method, target, headers, body: None })
+}
+
+// Поля в кавычках; \" внутри поля - экранированная кавычка
+fn quoted_fields(text: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut chars = text.chars();
+    while chars.by_ref().any(|c| c == '"') {
+        let mut field = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => field.extend(chars.next()),
+                c => field.push(c),
+            }
+        }
+        fields.push(field);
+    }
+    fields
+}
+
+// {"time": "2024-10-10T13:55:36Z", "method": "GET", "path": "/api?x=1", "headers": {...}, "body": "..."}
+fn parse_json_line(line: &str) -> Option<LogEntry> {
+    let record: Value = serde_json::from_str(line).ok()?;
+    let field = |names: &[&str]| names.iter().find_map(|name| record.get(*name)).filter(|value| !value.is_null());
+
+    let time = match field(&["time", "timestamp", "@timestamp", "ts", "time_local"])? {
+        Value::String(time) => DateTime::parse_from_rfc3339(time)
+            .or_else(|_| DateTime::parse_from_str(time, COMBINED_TIME_FORMAT))
+            .ok()?,
+        Value::Number(seconds) => {
+            let micros = (seconds.as_f64()? * 1_000_000.0) as i64;
+            Utc.timestamp_micros(micros).single()?.fixed_offset()
+        }
+        _ => return None,
+    };
+    let method = match field(&["method", "request_method"]).and_then(Value::as_str) {
+        Some(method) => HttpMethod::from_str(method, true).ok()?,
+        None => HttpMethod::GET,
+    };
+
+    let mut target = request_target(field(&["path", "uri", "request_uri", "url"])?.as_str()?)?;
+    if !target.contains('?')
+        && let Some(query) = field(&["query", "args", "query_string"]).and_then(Value::as_str)
+        && !query.is_empty()
+    {
+        target = format!("{}?{}", target, query.trim_start_matches('?'));
+    }
+
+    // Host, Content-Length и hop-by-hop заголовки выставляет клиент для стенда
+    let headers = field(&["headers"]).and_then(Value::as_object)
+        .map(|headers| headers.iter()
+            .filter(|(name, _)| keep_header(name))
+            .filter_map(|(name, value)| value.as_str().map(|value| format!("{}: {}", name, value)))
+            .collect())
+        .unwrap_or_default();
+    let body = field(&["body", "request_body"]).and_then(Value::as_str)
+        .filter(|body| !body.is_empty() && *body != "-")
+        .map(str::to_string);
+
+    Some(LogEntry { time, method, target, headers, body })
+}
+
+// Путь с query; у абсолютного URL (журналы прокси) хост отбрасывается
+fn request_target(target: &str) -> Option<String> {
+    if target.starts_with('/') {
+        return Some(target.to_string());
+    }
+    let url = Url::parse(target).ok()?;
+    Some(match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn read(log: &str, format: LogFormat, methods: &[HttpMethod], limit: Option<usize>) -> (Vec<ReplayJob>, LogSummary) {
+        let (sender, mut receiver) = mpsc::channel(64);
+        let summary = read_log(Cursor::new(log.to_string()), format, methods, limit, sender).unwrap();
+        let mut jobs = Vec::new();
+        while let Ok(job) = receiver.try_recv() {
+            jobs.push(job);
+        }
+        (jobs, summary)
+    }
+
+    fn combined(time: &str, request: &str) -> String {
+        format!("10.0.0.1 - - [10/Oct/2024:13:55:{} +0000] \"{}\" 200 512 \"-\" \"curl/8.0\"", time, request)
+    }
+
+    #[test]
+    fn combined_line_with_escaped_quotes() {
+        let line = r#"127.0.0.1 - frank [10/Oct/2024:13:55:36 +0300] "GET /search?q=%22a%22 HTTP/1.1" 200 2326 "https://example.com/?q=\"x\"" "Mozilla/5.0 (\"test\") Safari""#;
+        let entry = parse_combined(line).unwrap();
+        assert_eq!(entry.method, HttpMethod::GET);
+        assert_eq!(entry.target, "/search?q=%22a%22");
+        assert_eq!(entry.time.to_rfc3339(), "2024-10-10T13:55:36+03:00");
+        assert_eq!(entry.headers, [
+            r#"Referer: https://example.com/?q="x""#,
+            r#"User-Agent: Mozilla/5.0 ("test") Safari"#,
+        ]);
+        assert_eq!(quoted_fields(r#" "a\"b" 200 "" "\\""#), ["a\"b", "", "\\"]);
+    }
+
+    #[test]
+    fn absolute_url_targets_from_proxy_logs() {
+        let entry = parse_combined(&combined("36", "GET http://backend.internal:8080/api/items?page=2 HTTP/1.1")).unwrap();
+        assert_eq!(entry.target, "/api/items?page=2");
+        assert_eq!(entry.headers, ["User-Agent: curl/8.0"]);
+
+        let entry = parse_json_line(r#"{"time": "2024-10-10T13:55:36Z", "url": "https://example.com/a/b"}"#).unwrap();
+        assert_eq!(entry.target, "/a/b");
+
+        // CONNECT и прочий мусор вместо пути пропускаются
+        assert!(parse_combined(&combined("36", "CONNECT example.com:443 HTTP/1.1")).is_none());
+        assert!(parse_combined(&combined("36", "-")).is_none());
+    }
+
+    #[test]
+    fn json_line_with_epoch_time_and_query_field() {
+        let entry = parse_json_line(
+            r#"{"ts": 1728568536.25, "method": "post", "path": "/api", "query": "?x=1&y=2", "headers": {"Content-Type": "application/json", "Host": "prod", "Content-Length": "7"}, "body": "{\"a\":1}"}"#,
+        ).unwrap();
+        assert_eq!(entry.time.to_rfc3339(), "2024-10-10T13:55:36.250+00:00");
+        assert_eq!(entry.method, HttpMethod::POST);
+        assert_eq!(entry.target, "/api?x=1&y=2");
+        assert_eq!(entry.headers, ["Content-Type: application/json"]);
+        assert_eq!(entry.body.as_deref(), Some(r#"{"a":1}"#));
+
+        // Целое число секунд; query уже в пути - отдельное поле не добавляется
+        let entry = parse_json_line(r#"{"timestamp": 1728568536, "uri": "/api?z=3", "args": "x=1", "body": "-"}"#).unwrap();
+        assert_eq!(entry.time.timestamp(), 1_728_568_536);
+        assert_eq!(entry.method, HttpMethod::GET);
+        assert_eq!(entry.target, "/api?z=3");
+        assert!(entry.body.is_none());
+
+        assert!(parse_json_line(r#"{"path": "/api"}"#).is_none());
+        assert!(parse_json_line(r#"{"time": true, "path": "/api"}"#).is_none());
+        assert!(parse_json_line("not json").is_none());
+    }
+
+    #[test]
+    fn out_of_order_lines_are_sorted_inside_window() {
+        let log = [
+            combined("30", "GET /a HTTP/1.1"),
+            combined("32", "GET /c HTTP/1.1"),
+            combined("31", "GET /b HTTP/1.1"),
+            // Раньше первой строки: смещения считаются от самой ранней записи
+            combined("29", "GET /first HTTP/1.1"),
+            "broken line".to_string(),
+            combined("32", "GET /d HTTP/1.1"),
+        ].join("\n");
+        let (jobs, summary) = read(&log, LogFormat::Auto, &[], None);
+
+        let order: Vec<_> = jobs.iter().map(|job| (job.index, job.entry.target.as_str(), job.offset.as_secs())).collect();
+        assert_eq!(order, [(1, "/first", 0), (2, "/a", 1), (3, "/b", 2), (4, "/c", 3), (5, "/d", 3)]);
+        assert_eq!(summary.total, 5);
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(summary.span, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn limit_and_method_filter() {
+        let log = [
+            combined("30", "GET /a HTTP/1.1"),
+            combined("31", "POST /b HTTP/1.1"),
+            combined("32", "GET /c HTTP/1.1"),
+            combined("33", "GET /d HTTP/1.1"),
+        ].join("\n");
+
+        let (jobs, summary) = read(&log, LogFormat::Combined, &[], Some(2));
+        assert_eq!(jobs.iter().map(|job| job.entry.target.as_str()).collect::<Vec<_>>(), ["/a", "/b"]);
+        assert_eq!(summary.total, 2);
+
+        let (jobs, summary) = read(&log, LogFormat::Combined, &[HttpMethod::GET], Some(2));
+        assert_eq!(jobs.iter().map(|job| job.entry.target.as_str()).collect::<Vec<_>>(), ["/a", "/c"]);
+        assert_eq!(summary.skipped, 1);
+
+        let (jobs, _) = read(&log, LogFormat::Combined, &[], Some(0));
+        assert!(jobs.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+// Причина досрочной остановки теста
+#[derive(Debug, Clone)]
+pub(crate) enum StopReason {
+    // Ctrl+C или SIGTERM
+    Interrupted,
+    // Сработало условие --abort-on
+    AbortCondition(String),
+}
+
+impl StopReason {
+    // Код выхода процесса для прерванного теста
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            StopReason::Interrupted => 130,
+            StopReason::AbortCondition(_) => 3,
+        }
+    }
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Interrupted => write!(f, "прерван пользователем"),
+            StopReason::AbortCondition(condition) => {
+                write!(f, "сработало условие остановки {}", condition)
+            }
+        }
+    }
+}
+
+// Сторона, которая останавливает тест. Сохраняется только первая причина.
+#[derive(Clone)]
+pub(crate) struct StopHandle {
+    sender: Arc<watch::Sender<Option<StopReason>>>,
+}
+
+impl StopHandle {
+    pub(crate) fn stop(&self, reason: StopReason) {
+        self.sender.send_if_modified(|current| {
+            if current.is_none() {
+                *current = Some(reason);
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+// Сторона, которая следит за остановкой (планировщик, воркеры)
+#[derive(Clone)]
+pub(crate) struct StopSignal {
+    receiver: watch::Receiver<Option<StopReason>>,
+}
+
+impl StopSignal {
+    pub(crate) fn reason(&self) -> Option<StopReason> {
+        self.receiver.borrow().clone()
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.receiver.borrow().is_some()
+    }
+
+    // Завершается, когда тест остановлен
+    pub(crate) async fn stopped(&self) {
+        let mut receiver = self.receiver.clone();
+        // Ошибка означает, что StopHandle удален и остановки уже не будет
+        if receiver.wait_for(|reason| reason.is_some()).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+pub(crate) fn stop_channel() -> (StopHandle, StopSignal) {
+    let (sender, receiver) = watch::channel(None);
+    (StopHandle { sender: Arc::new(sender) }, StopSignal { receiver })
+}
+
+// Ctrl+C, а на unix еще и SIGTERM
+struct SignalListener {
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+}
+
+impl SignalListener {
+    fn new() -> std::io::Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            terminate: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?,
+        })
+    }
+
+    #[cfg(unix)]
+    async fn recv(&mut self) {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = self.terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn recv(&mut self) {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+// Первый сигнал останавливает тест, второй завершает процесс немедленно
+pub(crate) fn spawn_signal_listener(
+    handle: StopHandle,
+    grace_period: Duration,
+) -> std::io::Result<JoinHandle<()>> {
+    let mut listener = SignalListener::new()?;
+
+    Ok(tokio::spawn(async move {
+        listener.recv().await;
+        eprintln!(
+            "\n⚠️  Получен сигнал остановки: новые запросы не отправляются, ждем текущие до {} с (повторный Ctrl+C - немедленный выход)",
+            grace_period.as_secs_f32()
+        );
+        handle.stop(StopReason::Interrupted);
+
+        listener.recv().await;
+        eprintln!("⛔ Немедленный выход");
+        std::process::exit(StopReason::Interrupted.exit_code());
+    }))
+}
@@ -0,0 +1,758 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::guardrails::Guardrails;
+use crate::stream::StreamMetrics;
+
+// Класс ошибки для разбивки неудачных запросов в отчете
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum ErrorClass {
+    // Сервер ответил статусом не 2xx
+    Http,
+    Timeout,
+    Connect,
+    // Ошибка TLS-рукопожатия или проверки сертификата
+    Tls,
+    // Вызов gRPC завершился статусом не OK
+    Grpc,
+    // Ответ GraphQL с непустым массивом errors
+    GraphQl,
+    // Потоковый ответ закрыт раньше времени или без маркера завершения
+    Disconnect,
+    Other,
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ErrorClass::Http => "HTTP-статус",
+            ErrorClass::Timeout => "таймаут",
+            ErrorClass::Connect => "соединение",
+            ErrorClass::Tls => "TLS",
+            ErrorClass::Grpc => "статус gRPC",
+            ErrorClass::GraphQl => "ошибка GraphQL",
+            ErrorClass::Disconnect => "обрыв потока",
+            ErrorClass::Other => "прочие",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Класс транспортной ошибки по цепочке причин. Ошибки native-tls (reqwest, ws) и rustls (grpc)
+// распознаются по типу, в том числе внутри io::Error; ошибка сокета в конце цепочки - это соединение.
+// По тексту признаки TLS ищутся только для остальных ошибок. None - ошибка не транспортная.
+pub(crate) fn classify_transport(error: &(dyn std::error::Error + 'static)) -> Option<ErrorClass> {
+    let mut causes = Vec::new();
+    let mut cause = Some(error);
+    while let Some(error) = cause {
+        causes.push(error);
+        // source() у io::Error пропускает саму вложенную ошибку
+        cause = match error.downcast_ref::<std::io::Error>().and_then(std::io::Error::get_ref) {
+            Some(inner) => Some(inner),
+            None => error.source(),
+        };
+    }
+
+    if causes.iter().any(|cause| cause.is::<native_tls::Error>() || cause.is::<rustls::Error>()) {
+        return Some(ErrorClass::Tls);
+    }
+    let root = causes.last()?;
+    if root.downcast_ref::<std::io::Error>().is_some_and(|error| error.raw_os_error().is_some()) {
+        return Some(ErrorClass::Connect);
+    }
+    let is_tls = causes.iter().any(|cause| {
+        let message = cause.to_string().to_lowercase();
+        ["certificate", "handshake", "ssl", "tls"].iter().any(|marker| message.contains(marker))
+    });
+    if is_tls {
+        return Some(ErrorClass::Tls);
+    }
+    causes.iter()
+        .any(|cause| cause.downcast_ref::<hyper::Error>().is_some_and(hyper::Error::is_connect))
+        .then_some(ErrorClass::Connect)
+}
+
+// Результат запроса
+#[derive(Debug)]
+pub(crate) struct RequestResult {
+    pub(crate) user_id: usize,
+    pub(crate) success: bool,
+    pub(crate) duration: Duration,
+    pub(crate) status_code: Option<u16>,
+    pub(crate) error: Option<String>,
+    pub(crate) error_class: Option<ErrorClass>,
+    pub(crate) url: Arc<str>,
+    // Сколько редиректов пройдено и куда они привели
+    pub(crate) redirects: usize,
+    pub(crate) final_url: Option<Arc<str>>,
+    // Показатели потокового ответа (--stream)
+    pub(crate) stream: Option<StreamMetrics>,
+    //method: String,
+}
+
+// Гистограмма времени ответа с логарифмическими корзинами (погрешность около 3%).
+// Занимает несколько килобайт независимо от числа запросов.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LatencyHistogram {
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl LatencyHistogram {
+    // Корзин на каждую степень двойки
+    const SUB_BUCKETS: u64 = 32;
+
+    fn bucket_index(micros: u64) -> usize {
+        if micros < Self::SUB_BUCKETS {
+            return micros as usize;
+        }
+        let exponent = 63 - micros.leading_zeros() as u64;
+        let sub_bucket = (micros >> (exponent - 5)) & (Self::SUB_BUCKETS - 1);
+        ((exponent - 4) * Self::SUB_BUCKETS + sub_bucket) as usize
+    }
+
+    // Середина корзины в микросекундах
+    fn bucket_value(index: usize) -> u64 {
+        let index = index as u64;
+        if index < Self::SUB_BUCKETS {
+            return index;
+        }
+        let exponent = index / Self::SUB_BUCKETS + 4;
+        let sub_bucket = index % Self::SUB_BUCKETS;
+        let lower = (Self::SUB_BUCKETS + sub_bucket) << (exponent - 5);
+        lower + (1 << (exponent - 5)) / 2
+    }
+
+    pub(crate) fn record(&mut self, duration: Duration) {
+        let index = Self::bucket_index(duration.as_micros().min(u64::MAX as u128) as u64);
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.total += 1;
+    }
+
+    pub(crate) fn merge(&mut self, other: &LatencyHistogram) {
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other_count) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other_count;
+        }
+        self.total += other.total;
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub(crate) fn total(&self) -> u64 {
+        self.total
+    }
+
+    // Перцентиль в процентах (50.0, 99.0, ...)
+    pub(crate) fn percentile(&self, percentile: f64) -> Duration {
+        if self.total == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((percentile / 100.0) * self.total as f64).ceil().max(1.0) as u64;
+
+        let mut accumulated = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            accumulated += count;
+            if accumulated >= rank {
+                return Duration::from_micros(Self::bucket_value(index));
+            }
+        }
+        Duration::from_micros(Self::bucket_value(self.counts.len().saturating_sub(1)))
+    }
+}
+
+// Статистика теста
+#[derive(Debug, Default)]
+pub(crate) struct TestStats {
+    pub(crate) total_requests: usize,
+    pub(crate) successful: usize,
+    pub(crate) failed: usize,
+    pub(crate) min_duration: Duration,
+    pub(crate) max_duration: Duration,
+    pub(crate) total_duration: Duration,
+    pub(crate) avg_duration: Duration,
+    pub(crate) status_codes: HashMap<u16, usize>,
+    pub(crate) error_classes: HashMap<ErrorClass, usize>,
+    pub(crate) latency: LatencyHistogram,
+}
+
+impl TestStats {
+    pub(crate) fn new() -> Self {
+        Self {
+            min_duration: Duration::from_secs(u64::MAX),
+            max_duration: Duration::from_secs(0),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn add_result(&mut self, result: &RequestResult) {
+        self.total_requests += 1;
+
+        if result.success {
+            self.successful += 1;
+
+            if let Some(status) = result.status_code {
+                *self.status_codes.entry(status).or_insert(0) += 1;
+            }
+
+            self.total_duration += result.duration;
+            self.latency.record(result.duration);
+
+            if result.duration < self.min_duration {
+                self.min_duration = result.duration;
+            }
+            if result.duration > self.max_duration {
+                self.max_duration = result.duration;
+            }
+        } else {
+            self.failed += 1;
+
+            if let Some(error_class) = result.error_class {
+                *self.error_classes.entry(error_class).or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub(crate) fn calculate_final(&mut self) {
+        if self.successful > 0 {
+            self.avg_duration = self.total_duration / self.successful as u32;
+        }
+    }
+
+    pub(crate) fn print_summary(&self) {
+        println!("\n📊 Результаты теста:");
+        println!("{}", "=".repeat(40));
+        println!("Всего запросов: {}", self.total_requests);
+        println!("Успешно: {}", self.successful);
+        println!("Неудачно: {}", self.failed);
+
+        if self.total_requests > 0 {
+            println!("Успешность: {:.1}%",
+                (self.successful as f32 / self.total_requests as f32) * 100.0);
+        }
+
+        if !self.status_codes.is_empty() {
+            println!("\n📈 Коды ответа:");
+            let mut codes: Vec<_> = self.status_codes.iter().collect();
+            codes.sort_by_key(|(code, _)| *code);
+            for (code, count) in codes {
+                println!("  {}: {} запросов", code, count);
+            }
+        }
+
+        if !self.error_classes.is_empty() {
+            println!("\n❗ Ошибки по типам:");
+            let mut classes: Vec<_> = self.error_classes.iter().collect();
+            classes.sort_by_key(|(class, _)| *class);
+            for (class, count) in classes {
+                println!("  {}: {} запросов", class, count);
+            }
+        }
+
+        if self.successful > 0 {
+            println!("\n⏱️  Время ответа:");
+            println!("  Минимальное: {:.2}ms", self.min_duration.as_millis());
+            println!("  Максимальное: {:.2}ms", self.max_duration.as_millis());
+            println!("  Среднее: {:.2}ms", self.avg_duration.as_millis());
+            println!("  p50: {:.2}ms", self.latency.percentile(50.0).as_millis());
+            println!("  p90: {:.2}ms", self.latency.percentile(90.0).as_millis());
+            println!("  p95: {:.2}ms", self.latency.percentile(95.0).as_millis());
+            println!("  p99: {:.2}ms", self.latency.percentile(99.0).as_millis());
+        }
+    }
+}
+
+// Счетчики для одного URL или одной секунды теста
+#[derive(Debug, Default, Clone)]
+pub(crate) struct BucketStats {
+    pub(crate) total: usize,
+    pub(crate) successful: usize,
+    pub(crate) total_duration: Duration,
+}
+
+impl BucketStats {
+    fn add_result(&mut self, result: &RequestResult) {
+        self.total += 1;
+        if result.success {
+            self.successful += 1;
+            self.total_duration += result.duration;
+        }
+    }
+
+    pub(crate) fn success_rate(&self) -> f32 {
+        if self.total > 0 {
+            (self.successful as f32 / self.total as f32) * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    pub(crate) fn avg_duration(&self) -> Duration {
+        if self.successful > 0 {
+            self.total_duration / self.successful as u32
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+// Сколько разных адресов редиректа хранить для одного URL, остальные считаются вместе
+const MAX_REDIRECT_TARGETS: usize = 100;
+// Сколько самых частых адресов показывать в отчете
+const SHOWN_REDIRECT_TARGETS: usize = 5;
+
+// Редиректы запросов к одному URL
+#[derive(Debug, Default)]
+pub(crate) struct RedirectStats {
+    redirected: usize,
+    hops: usize,
+    targets: HashMap<Arc<str>, usize>,
+    other_targets: usize,
+}
+
+impl RedirectStats {
+    fn add_result(&mut self, result: &RequestResult) {
+        self.redirected += 1;
+        self.hops += result.redirects;
+
+        let Some(target) = &result.final_url else { return };
+        if let Some(count) = self.targets.get_mut(target) {
+            *count += 1;
+        } else if self.targets.len() < MAX_REDIRECT_TARGETS {
+            self.targets.insert(target.clone(), 1);
+        } else {
+            self.other_targets += 1;
+        }
+    }
+
+    // prefix - начало строки с итогом, indent - отступ строк с адресами
+    fn print(&self, total: usize, prefix: &str, indent: &str) {
+        println!("{}Редиректы: {} из {} запросов, в среднем {:.1} на запрос с редиректом",
+            prefix, self.redirected, total, self.hops as f64 / self.redirected as f64);
+
+        let mut targets: Vec<_> = self.targets.iter().collect();
+        targets.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (target, count) in targets.iter().take(SHOWN_REDIRECT_TARGETS) {
+            println!("{}  → {}: {}", indent, target, count);
+        }
+
+        let rest = self.other_targets
+            + targets.iter().skip(SHOWN_REDIRECT_TARGETS).map(|(_, count)| **count).sum::<usize>();
+        if rest > 0 {
+            println!("{}  → другие адреса: {}", indent, rest);
+        }
+    }
+}
+
+// Показатели всех потоковых ответов теста
+#[derive(Debug, Default)]
+pub(crate) struct StreamStats {
+    streams: usize,
+    disconnects: usize,
+    events: usize,
+    first_event: LatencyHistogram,
+    gaps: LatencyHistogram,
+    durations: LatencyHistogram,
+}
+
+impl StreamStats {
+    fn add_result(&mut self, result: &RequestResult) {
+        let Some(metrics) = &result.stream else { return };
+        self.streams += 1;
+        self.disconnects += usize::from(result.error_class == Some(ErrorClass::Disconnect));
+        self.events += metrics.events;
+        if let Some(first_event) = metrics.first_event {
+            self.first_event.record(first_event);
+        }
+        self.gaps.merge(&metrics.gaps);
+        self.durations.record(metrics.duration);
+    }
+
+    fn print_percentiles(name: &str, histogram: &LatencyHistogram) {
+        if histogram.is_empty() {
+            return;
+        }
+        println!("  {}: p50 {}ms, p90 {}ms, p99 {}ms",
+            name,
+            histogram.percentile(50.0).as_millis(),
+            histogram.percentile(90.0).as_millis(),
+            histogram.percentile(99.0).as_millis());
+    }
+}
+
+// Случайная выборка неудачных запросов фиксированного размера (reservoir sampling)
+#[derive(Debug)]
+pub(crate) struct FailureSamples {
+    capacity: usize,
+    seen: usize,
+    samples: Vec<RequestResult>,
+}
+
+impl FailureSamples {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            samples: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn add(&mut self, result: RequestResult) {
+        self.seen += 1;
+        if self.samples.len() < self.capacity {
+            self.samples.push(result);
+        } else {
+            let index = rand::thread_rng().gen_range(0..self.seen);
+            if index < self.capacity {
+                self.samples[index] = result;
+            }
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &RequestResult> {
+        self.samples.iter()
+    }
+}
+
+// Потоковая агрегация результатов: память не растет с числом запросов
+#[derive(Debug)]
+pub(crate) struct Aggregator {
+    pub(crate) stats: TestStats,
+    pub(crate) url_stats: HashMap<Arc<str>, BucketStats>,
+    pub(crate) per_second: Vec<BucketStats>,
+    pub(crate) failures: FailureSamples,
+    pub(crate) redirects: HashMap<Arc<str>, RedirectStats>,
+    pub(crate) streams: StreamStats,
+    started: Instant,
+}
+
+impl Aggregator {
+    pub(crate) fn new(failure_samples: usize) -> Self {
+        Self {
+            stats: TestStats::new(),
+            url_stats: HashMap::new(),
+            per_second: Vec::new(),
+            failures: FailureSamples::new(failure_samples),
+            redirects: HashMap::new(),
+            streams: StreamStats::default(),
+            started: Instant::now(),
+        }
+    }
+
+    pub(crate) fn add_result(&mut self, result: RequestResult) {
+        self.stats.add_result(&result);
+
+        match self.url_stats.get_mut(&result.url) {
+            Some(bucket) => bucket.add_result(&result),
+            None => {
+                let mut bucket = BucketStats::default();
+                bucket.add_result(&result);
+                self.url_stats.insert(result.url.clone(), bucket);
+            }
+        }
+
+        let second = self.started.elapsed().as_secs() as usize;
+        if self.per_second.len() <= second {
+            self.per_second.resize(second + 1, BucketStats::default());
+        }
+        self.per_second[second].add_result(&result);
+
+        if result.redirects > 0 {
+            self.redirects.entry(result.url.clone()).or_default().add_result(&result);
+        }
+        self.streams.add_result(&result);
+
+        if !result.success {
+            self.failures.add(result);
+        }
+    }
+
+    // Итоги за завершившуюся секунду теста (нумерация с единицы)
+    fn print_second(&self, second: usize) {
+        let bucket = self.per_second.get(second - 1).cloned().unwrap_or_default();
+
+        println!("\n🕒 Секунда {}:", second);
+        println!("  Запросов: {}/{} успешно", bucket.successful, bucket.total);
+        if bucket.successful > 0 {
+            println!("  Среднее время: {:.2}ms", bucket.avg_duration().as_millis());
+        }
+    }
+
+    pub(crate) fn print_url_summary(&self) {
+        println!("\n📈 Статистика по URL:");
+        println!("{}", "-".repeat(40));
+
+        for (url, bucket) in &self.url_stats {
+            println!("🔗 {}", url);
+            println!("   Запросов: {}/{} успешно ({:.1}%)",
+                bucket.successful, bucket.total, bucket.success_rate());
+            if bucket.successful > 0 {
+                println!("   Среднее время: {:.2}ms", bucket.avg_duration().as_millis());
+            }
+            if let Some(redirects) = self.redirects.get(url) {
+                redirects.print(bucket.total, "   ", "   ");
+            }
+            println!();
+        }
+    }
+
+    // Итоги по группам URL (поле group в файле multi); URL без группы не учитываются
+    pub(crate) fn print_group_summary(&self, groups: &HashMap<Arc<str>, String>) {
+        let mut totals: BTreeMap<&str, BucketStats> = BTreeMap::new();
+        for (url, bucket) in &self.url_stats {
+            if let Some(group) = groups.get(url) {
+                let total = totals.entry(group.as_str()).or_default();
+                total.total += bucket.total;
+                total.successful += bucket.successful;
+                total.total_duration += bucket.total_duration;
+            }
+        }
+        if totals.is_empty() {
+            return;
+        }
+
+        println!("📂 Статистика по группам:");
+        println!("{}", "-".repeat(40));
+        for (group, bucket) in totals {
+            println!("  {}: {}/{} успешно ({:.1}%), среднее время {:.2}ms",
+                group, bucket.successful, bucket.total, bucket.success_rate(),
+                bucket.avg_duration().as_millis());
+        }
+        println!();
+    }
+
+    // Редиректы для режимов с одним URL (burst, rps)
+    pub(crate) fn print_redirects(&self) {
+        for (url, redirects) in &self.redirects {
+            let total = self.url_stats.get(url).map_or(0, |bucket| bucket.total);
+            println!();
+            redirects.print(total, "↪️  ", "   ");
+        }
+    }
+
+    // Итоги потоковых ответов (--stream); elapsed - длительность теста
+    pub(crate) fn print_streams(&self, elapsed: Duration) {
+        let streams = &self.streams;
+        if streams.streams == 0 {
+            return;
+        }
+
+        println!("\n📡 Потоки:");
+        println!("  Потоков: {}, обрывов: {}", streams.streams, streams.disconnects);
+        println!("  Событий: {} (в среднем {:.1} на поток, {:.1} в секунду)",
+            streams.events,
+            streams.events as f64 / streams.streams as f64,
+            streams.events as f64 / elapsed.as_secs_f64().max(f64::EPSILON));
+        StreamStats::print_percentiles("До первого события", &streams.first_event);
+        StreamStats::print_percentiles("Паузы между событиями", &streams.gaps);
+        StreamStats::print_percentiles("Длительность потока", &streams.durations);
+    }
+
+    pub(crate) fn print_failures(&self) {
+        if self.stats.failed == 0 {
+            return;
+        }
+
+        println!("\n🔍 Неудачные запросы (выборка, до {}):", self.failures.capacity);
+        for result in self.failures.iter() {
+            println!("  Пользователь {} ({}): {}",
+                result.user_id, result.url, result.error.as_deref().unwrap_or("Unknown"));
+        }
+    }
+}
+
+// Запускает задачу-агрегатор, читающую результаты из канала.
+// С live_report печатает итоги каждой завершившейся секунды,
+// guardrails проверяются по тем же секундным отметкам.
+pub(crate) fn spawn_aggregator(
+    mut results: mpsc::Receiver<RequestResult>,
+    failure_samples: usize,
+    live_report: bool,
+    mut guardrails: Option<Guardrails>,
+) -> JoinHandle<Aggregator> {
+    tokio::spawn(async move {
+        let mut aggregator = Aggregator::new(failure_samples);
+        let mut report_ticker = tokio::time::interval_at(
+            tokio::time::Instant::now() + Duration::from_secs(1),
+            Duration::from_secs(1),
+        );
+        let mut second = 0;
+        let ticking = live_report || guardrails.is_some();
+
+        loop {
+            tokio::select! {
+                result = results.recv() => {
+                    let Some(request_result) = result else { break };
+                    if let Some(guardrails) = guardrails.as_mut() {
+                        guardrails.add_result(&request_result);
+                    }
+                    aggregator.add_result(request_result);
+                }
+                _ = report_ticker.tick(), if ticking => {
+                    second += 1;
+                    if live_report {
+                        aggregator.print_second(second);
+                    }
+                    if let Some(guardrails) = guardrails.as_mut() {
+                        guardrails.on_second();
+                    }
+                }
+            }
+        }
+
+        aggregator.stats.calculate_final();
+        aggregator
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ширина корзины, в которую попадает значение: до 64 мкс корзины точные
+    fn bucket_width(micros: u64) -> u64 {
+        if micros < 2 * LatencyHistogram::SUB_BUCKETS {
+            return 1;
+        }
+        let exponent = 63 - micros.leading_zeros() as u64;
+        1 << (exponent - 5)
+    }
+
+    fn histogram(values: &[u64]) -> LatencyHistogram {
+        let mut histogram = LatencyHistogram::default();
+        for &micros in values {
+            histogram.record(Duration::from_micros(micros));
+        }
+        histogram
+    }
+
+    fn assert_percentiles_within_bucket(mut values: Vec<u64>) {
+        let histogram = histogram(&values);
+        values.sort_unstable();
+        for percentile in [1.0, 10.0, 50.0, 90.0, 95.0, 99.0, 99.9, 100.0] {
+            let rank = ((percentile / 100.0) * values.len() as f64).ceil() as usize;
+            let exact = values[rank - 1];
+            let estimate = histogram.percentile(percentile).as_micros() as u64;
+            assert!(
+                exact.abs_diff(estimate) <= bucket_width(exact),
+                "p{}: точное {} мкс, оценка {} мкс",
+                percentile, exact, estimate
+            );
+        }
+    }
+
+    #[test]
+    fn bucket_boundaries() {
+        assert_eq!(LatencyHistogram::bucket_index(0), 0);
+        assert_eq!(LatencyHistogram::bucket_index(1), 1);
+        assert_eq!(LatencyHistogram::bucket_index(31), 31);
+        assert_eq!(LatencyHistogram::bucket_index(32), 32);
+        assert_eq!(LatencyHistogram::bucket_index(63), 63);
+        // С 64 мкс корзина шириной 2, дальше ширина удваивается на каждой степени двойки
+        assert_eq!(LatencyHistogram::bucket_index(64), 64);
+        assert_eq!(LatencyHistogram::bucket_index(65), 64);
+        assert_eq!(LatencyHistogram::bucket_index(66), 65);
+        for exponent in 6..64 {
+            let power = 1u64 << exponent;
+            let first = (exponent - 4) * 32;
+            assert_eq!(LatencyHistogram::bucket_index(power), first as usize);
+            assert_eq!(LatencyHistogram::bucket_index(power - 1), first as usize - 1);
+        }
+        assert_eq!(LatencyHistogram::bucket_index(u64::MAX), 59 * 32 + 31);
+    }
+
+    #[test]
+    fn bucket_value_stays_inside_its_bucket() {
+        for index in 0..=LatencyHistogram::bucket_index(u64::MAX) {
+            let value = LatencyHistogram::bucket_value(index);
+            assert_eq!(LatencyHistogram::bucket_index(value), index);
+        }
+    }
+
+    #[test]
+    fn record_clamps_huge_durations() {
+        let histogram = histogram(&[]);
+        assert_eq!(histogram.percentile(99.0), Duration::ZERO);
+
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(Duration::MAX);
+        assert_eq!(histogram.total(), 1);
+        let estimate = histogram.percentile(50.0).as_micros() as u64;
+        assert!(u64::MAX - estimate <= bucket_width(u64::MAX));
+    }
+
+    #[test]
+    fn percentile_error_within_one_sub_bucket() {
+        // Равномерное распределение от 1 мкс до 100 мс
+        assert_percentiles_within_bucket((1..=100_000).collect());
+        // Экспоненциальный рост от 1 мс до десятков секунд
+        assert_percentiles_within_bucket((0..20_000).map(|i| (1000.0 * 1.0005f64.powi(i)) as u64).collect());
+        // Бимодальное: быстрые ответы из кэша и медленные из базы
+        assert_percentiles_within_bucket((0..10_000).map(|i| if i % 10 == 0 { 2_000_000 + i * 7 } else { 150 + i % 50 }).collect());
+        // Все значения одинаковые
+        assert_percentiles_within_bucket(vec![12_345; 1000]);
+    }
+
+    #[test]
+    fn merge_matches_single_histogram() {
+        let values: Vec<u64> = (0..5000).map(|i| 100 + i * 13).collect();
+        let (left, right) = values.split_at(1234);
+        let mut merged = histogram(left);
+        merged.merge(&histogram(right));
+        let whole = histogram(&values);
+        assert_eq!(merged.total(), whole.total());
+        for percentile in [50.0, 90.0, 99.0] {
+            assert_eq!(merged.percentile(percentile), whole.percentile(percentile));
+        }
+    }
+
+    fn failure(user_id: usize) -> RequestResult {
+        RequestResult {
+            user_id,
+            success: false,
+            duration: Duration::from_millis(1),
+            status_code: Some(500),
+            error: None,
+            error_class: Some(ErrorClass::Http),
+            url: Arc::from("http://localhost/"),
+            redirects: 0,
+            final_url: None,
+            stream: None,
+        }
+    }
+
+    #[test]
+    fn reservoir_never_exceeds_capacity() {
+        let mut samples = FailureSamples::new(5);
+        for user_id in 0..3 {
+            samples.add(failure(user_id));
+        }
+        assert_eq!(samples.iter().map(|result| result.user_id).collect::<Vec<_>>(), [0, 1, 2]);
+
+        for user_id in 3..10_000 {
+            samples.add(failure(user_id));
+            assert!(samples.iter().count() <= 5);
+        }
+        assert_eq!(samples.iter().count(), 5);
+        assert_eq!(samples.seen, 10_000);
+
+        let mut disabled = FailureSamples::new(0);
+        for user_id in 0..100 {
+            disabled.add(failure(user_id));
+        }
+        assert_eq!(disabled.iter().count(), 0);
+    }
+}
@@ -0,0 +1,178 @@
+use std::time::{Duration, Instant};
+
+use load_test::StreamConfig;
+use reqwest::header::CONTENT_TYPE;
+
+use crate::stats::{ErrorClass, LatencyHistogram};
+
+// Показатели одного потокового ответа
+#[derive(Debug, Default)]
+pub(crate) struct StreamMetrics {
+    // От начала запроса до первого события
+    pub(crate) first_event: Option<Duration>,
+    // Паузы между соседними событиями: гистограмма, а не список, чтобы долгий поток не копил память
+    pub(crate) gaps: LatencyHistogram,
+    pub(crate) events: usize,
+    // От начала запроса до закрытия потока
+    pub(crate) duration: Duration,
+}
+
+// Читает поток до конца, до маркера завершения или до истечения settings.duration.
+// Для text/event-stream событием считается событие SSE, иначе - каждый полученный chunk.
+// Пауза без данных дольше idle_timeout считается таймаутом.
+pub(crate) async fn read_stream(
+    mut response: reqwest::Response,
+    settings: &StreamConfig,
+    start: Instant,
+    idle_timeout: Duration,
+) -> (StreamMetrics, Option<(ErrorClass, String)>) {
+    let is_sse = response.headers().get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    let deadline = settings.duration
+        .map(|duration| tokio::time::Instant::now() + Duration::from_secs(duration));
+
+    let mut metrics = StreamMetrics::default();
+    let mut parser = SseParser::default();
+    let mut last_event = None;
+    let mut ended = false;
+
+    let error = loop {
+        let idle_deadline = tokio::time::Instant::now() + idle_timeout;
+        let wait_until = deadline.map_or(idle_deadline, |deadline| deadline.min(idle_deadline));
+        let chunk = match tokio::time::timeout_at(wait_until, response.chunk()).await {
+            Ok(chunk) => chunk,
+            // Поток продержался нужное время
+            Err(_) if deadline.is_some_and(|deadline| deadline <= idle_deadline) => break None,
+            Err(_) => {
+                let message = format!("Нет данных дольше {} секунд", idle_timeout.as_secs());
+                break Some((ErrorClass::Timeout, message));
+            }
+        };
+
+        let chunk = match chunk {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break closed_early(settings, deadline),
+            Err(e) => break Some((ErrorClass::Disconnect, format!("Обрыв потока: {}", e))),
+        };
+
+        let events = if is_sse {
+            parser.push(&chunk)
+        } else {
+            vec![String::from_utf8_lossy(&chunk).into_owned()]
+        };
+        let now = Instant::now();
+        for event in events {
+            metrics.events += 1;
+            match last_event {
+                Some(last) => metrics.gaps.record(now - last),
+                None => metrics.first_event = Some(now - start),
+            }
+            last_event = Some(now);
+            ended |= settings.end_marker.as_deref().is_some_and(|marker| event.contains(marker));
+        }
+        if ended {
+            break None;
+        }
+    };
+
+    metrics.duration = start.elapsed();
+    (metrics, error)
+}
+
+// Сервер закрыл поток сам: это обрыв, если ожидался маркер или поток должен был жить дольше
+fn closed_early(
+    settings: &StreamConfig,
+    deadline: Option<tokio::time::Instant>,
+) -> Option<(ErrorClass, String)> {
+    if let Some(marker) = &settings.end_marker {
+        return Some((ErrorClass::Disconnect, format!("Поток закрыт до маркера {}", marker)));
+    }
+    if let Some(deadline) = deadline
+        && tokio::time::Instant::now() < deadline
+    {
+        let left = deadline - tokio::time::Instant::now();
+        return Some((ErrorClass::Disconnect, format!("Поток закрыт за {:.1}s до срока", left.as_secs_f32())));
+    }
+    None
+}
+
+// Разбивает поток text/event-stream на события. Возвращает поле data событий;
+// блоки только из комментариев (keep-alive) событиями не считаются.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    // Чанк закончился на \r: \n в начале следующего - продолжение того же CRLF
+    after_cr: bool,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        // Строки заканчиваются на CRLF, LF или CR - приводим к LF
+        for &byte in chunk {
+            if std::mem::take(&mut self.after_cr) && byte == b'\n' {
+                continue;
+            }
+            self.after_cr = byte == b'\r';
+            self.buffer.push(if self.after_cr { b'\n' } else { byte });
+        }
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|window| window == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let block = String::from_utf8_lossy(&block[..end]);
+
+            let mut is_event = false;
+            let mut data = Vec::new();
+            for line in block.lines().filter(|line| !line.is_empty() && !line.starts_with(':')) {
+                is_event = true;
+                if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.strip_prefix(' ').unwrap_or(value));
+                }
+            }
+            if is_event {
+                events.push(data.join("\n"));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_all(chunks: &[&str]) -> Vec<String> {
+        let mut parser = SseParser::default();
+        chunks.iter().flat_map(|chunk| parser.push(chunk.as_bytes())).collect()
+    }
+
+    #[test]
+    fn event_split_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"event: tick\nda").is_empty());
+        assert!(parser.push(b"ta: 1\n").is_empty());
+        assert_eq!(parser.push(b"\ndata: 2\n\ndata:"), ["1", "2"]);
+        assert_eq!(parser.push(b"3\n\n"), ["3"]);
+    }
+
+    #[test]
+    fn crlf_and_cr_line_endings() {
+        assert_eq!(push_all(&["data: a\r\n\r\ndata: b\r\n\r\n"]), ["a", "b"]);
+        assert_eq!(push_all(&["data: a\r\rdata: b\r\r"]), ["a", "b"]);
+        // CRLF разрезан между чанками
+        assert_eq!(push_all(&["data: a\r", "\n\r", "\n"]), ["a"]);
+    }
+
+    #[test]
+    fn comment_only_blocks_are_not_events() {
+        assert_eq!(push_all(&[": keep-alive\n\n", ":\n\n", "data: x\n: inline comment\n\n"]), ["x"]);
+        // Событие без data тоже событие
+        assert_eq!(push_all(&["event: ping\n\n"]), [""]);
+    }
+
+    #[test]
+    fn multi_line_data() {
+        assert_eq!(push_all(&["data: first\ndata:second\ndata:  third\nid: 7\n\n"]), ["first\nsecond\n third"]);
+    }
+}
@@ -0,0 +1,136 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::client::connect::{Connected, Connection};
+use hyper::{Body, Uri};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::stats::ErrorClass;
+
+const UNIX_SCHEME: &str = "unix://";
+
+// Разбирает адрес вида unix:///var/run/app.sock:/api/health.
+// Возвращает путь к сокету и HTTP URL, который уходит в запрос; None - обычный URL.
+pub(crate) fn split_url(url: &str) -> Result<Option<(String, String)>, String> {
+    let Some(rest) = url.strip_prefix(UNIX_SCHEME) else { return Ok(None) };
+    let (socket_path, path) = match rest.split_once(':') {
+        Some((socket_path, path)) => (socket_path, path),
+        None => (rest, "/"),
+    };
+    if socket_path.is_empty() {
+        return Err(format!("Не указан путь к сокету: {}", url));
+    }
+    if !path.starts_with('/') {
+        return Err(format!("Путь запроса должен начинаться с /: {}", url));
+    }
+    Ok(Some((socket_path.to_string(), format!("http://localhost{}", path))))
+}
+
+// HTTP-клиент поверх Unix-сокета. Запрос собирается как обычный reqwest::Request,
+// ответ возвращается как reqwest::Response, так что редиректы, потоки и GraphQL
+// обрабатываются в make_request одинаково для TCP и сокетов.
+#[derive(Debug, Clone)]
+pub(crate) struct UnixClient {
+    client: hyper::Client<UnixConnector>,
+}
+
+impl UnixClient {
+    pub(crate) fn new(socket_path: &str) -> Result<Self, String> {
+        if cfg!(not(unix)) {
+            return Err("Unix-сокеты не поддерживаются на этой платформе".to_string());
+        }
+        let connector = UnixConnector { socket_path: Arc::from(socket_path) };
+        Ok(Self { client: hyper::Client::builder().build(connector) })
+    }
+
+    pub(crate) async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, (ErrorClass, String)> {
+        let uri: Uri = request.url().as_str().parse()
+            .map_err(|e| (ErrorClass::Other, format!("Некорректный URL: {}", e)))?;
+        let body = request.body()
+            .and_then(|body| body.as_bytes())
+            .map(|bytes| Body::from(bytes.to_vec()))
+            .unwrap_or_else(Body::empty);
+
+        let mut builder = hyper::Request::builder()
+            .method(request.method().clone())
+            .uri(uri);
+        if let Some(headers) = builder.headers_mut() {
+            *headers = request.headers().clone();
+        }
+        let request = builder.body(body)
+            .map_err(|e| (ErrorClass::Other, e.to_string()))?;
+
+        match self.client.request(request).await {
+            Ok(response) => Ok(reqwest::Response::from(response)),
+            Err(e) if e.is_connect() => Err((ErrorClass::Connect, e.to_string())),
+            Err(e) => Err((ErrorClass::Other, e.to_string())),
+        }
+    }
+}
+
+// Коннектор hyper: на любой URI открывает соединение с заданным сокетом
+#[derive(Debug, Clone)]
+struct UnixConnector {
+    socket_path: Arc<str>,
+}
+
+impl hyper::service::Service<Uri> for UnixConnector {
+    type Response = UnixConnection;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let socket_path = self.socket_path.clone();
+        Box::pin(async move { connect(&socket_path).await })
+    }
+}
+
+#[cfg(unix)]
+async fn connect(socket_path: &str) -> std::io::Result<UnixConnection> {
+    let stream = tokio::net::UnixStream::connect(socket_path).await
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", socket_path, e)))?;
+    Ok(UnixConnection(stream))
+}
+
+#[cfg(not(unix))]
+async fn connect(_socket_path: &str) -> std::io::Result<UnixConnection> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Unix-сокеты не поддерживаются на этой платформе"))
+}
+
+#[cfg(unix)]
+struct UnixConnection(tokio::net::UnixStream);
+
+#[cfg(not(unix))]
+struct UnixConnection(tokio::io::DuplexStream);
+
+impl Connection for UnixConnection {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+impl AsyncRead for UnixConnection {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixConnection {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,525 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use clap::Args;
+use futures_util::{SinkExt, StreamExt};
+use load_test::{AbortCondition, ClientOptions};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::Connector;
+use url::{Host, Url};
+
+use crate::auth::{AuthProviders, Authorization};
+use crate::client;
+use crate::connect::TcpConnector;
+use crate::guardrails::Guardrails;
+use crate::shutdown::{StopHandle, StopSignal};
+use crate::stats::{spawn_aggregator, ErrorClass, RequestResult, TestStats};
+use crate::{print_stop_notice, FAILURE_SAMPLES};
+
+const DEFAULT_MESSAGE: &str = r#"{"id":"{{correlationId}}","ts":"{{timestamp}}"}"#;
+// Как часто проверять сообщения без ответа
+const EXPIRE_CHECK: Duration = Duration::from_millis(100);
+
+#[derive(Args)]
+pub(crate) struct WsConfig {
+    /// URL WebSocket (ws:// или wss://)
+    #[arg(short = 'U', long, default_value = "ws://localhost:3000/ws")]
+    url: String,
+
+    /// Количество одновременных соединений
+    #[arg(short, long, default_value_t = 10)]
+    connections: usize,
+
+    /// За сколько секунд равномерно открыть все соединения
+    #[arg(long, default_value_t = 0.0)]
+    ramp_up: f64,
+
+    /// Сообщений в секунду на одно соединение
+    #[arg(short, long, default_value_t = 1.0)]
+    rate: f64,
+
+    /// Длительность теста в секундах
+    #[arg(short, long, default_value_t = 30)]
+    duration: u64,
+
+    /// Шаблон сообщения: {{correlationId}}, {{userId}} (номер соединения), {{timestamp}}, {{uuid}}
+    #[arg(short, long, default_value = DEFAULT_MESSAGE)]
+    message: String,
+
+    /// Поле JSON-ответа с идентификатором сообщения. Без {{correlationId}} в шаблоне
+    /// ответы сопоставляются с сообщениями по порядку
+    #[arg(long, default_value = "id")]
+    correlation_field: String,
+
+    /// Заголовки handshake в формате "Header: Value" (можно указать несколько)
+    #[arg(short = 'H', long)]
+    headers: Vec<String>,
+
+    /// Таймаут подключения и ожидания ответа в секундах
+    #[arg(short, long, default_value_t = 10)]
+    timeout: u64,
+
+    /// Остановить тест по условию, например "error_rate>5%" или "p99>800ms for 10s"
+    #[arg(long = "abort-on")]
+    abort_on: Vec<AbortCondition>,
+
+    /// Печатать строку на каждое сообщение
+    #[arg(short, long)]
+    verbose: bool,
+
+    #[command(flatten)]
+    client: ClientOptions,
+}
+
+// Общие настройки всех соединений
+struct WsContext {
+    config: WsConfig,
+    url: Arc<str>,
+    timeout: Duration,
+    deadline: tokio::time::Instant,
+    // Сколько ждать ответы на отправленные сообщения после окончания теста
+    drain: Duration,
+    correlate_by_id: bool,
+    transport: Transport,
+    stop: StopSignal,
+}
+
+// Куда и как подключаться: TCP и TLS по общим настройкам клиента.
+// С --tls-server-name URL handshake содержит это имя, а соединение идет на хост исходного URL.
+struct Transport {
+    tcp: TcpConnector,
+    tls: native_tls::TlsConnector,
+    request_url: String,
+    host: String,
+    port: u16,
+    connect_timeout: Duration,
+    auth: Option<Authorization>,
+}
+
+// Итог одного соединения; connect - None, если тест остановлен до подключения
+struct ConnectionOutcome {
+    connect: Option<RequestResult>,
+    dropped: bool,
+}
+
+// Сообщение, ожидающее ответа
+struct PendingMessage {
+    id: String,
+    sent: Instant,
+}
+
+pub(crate) async fn run(
+    config: WsConfig,
+    grace_period: Duration,
+    stop_handle: StopHandle,
+    stop: StopSignal,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if config.connections == 0 {
+        return Err("Количество соединений должно быть больше нуля".into());
+    }
+    // Интервал 1/rate должен быть конечным и ненулевым: иначе Duration и interval паникуют
+    let interval = Some(config.rate)
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .and_then(|rate| Duration::try_from_secs_f64(1.0 / rate).ok())
+        .filter(|interval| !interval.is_zero());
+    if interval.is_none() {
+        return Err(format!("Некорректная частота сообщений {}: нужно конечное число больше нуля", config.rate).into());
+    }
+    if !(config.ramp_up.is_finite() && config.ramp_up >= 0.0) || Duration::try_from_secs_f64(config.ramp_up).is_err() {
+        return Err(format!("Некорректный --ramp-up {}: нужно конечное число секунд не меньше нуля", config.ramp_up).into());
+    }
+
+    // Пул, редиректы и подпись относятся только к HTTP-запросам
+    client::reject_unsupported("ws", &config.client, &[])?;
+    let transport = transport(&config).await?;
+
+    println!("🔌 WebSocket: {} соединений", config.connections);
+    println!("🔗 URL: {}", config.url);
+    println!("🔧 Соединения: {}", client::describe(&config.client));
+    println!("📨 {} сообщ./с на соединение в течение {} секунд", config.rate, config.duration);
+    if config.ramp_up > 0.0 {
+        println!("📶 Открытие соединений за {} секунд", config.ramp_up);
+    }
+    println!("⏱️  Таймаут: {} секунд", config.timeout);
+    if !config.abort_on.is_empty() {
+        println!("🛑 Условия остановки:");
+        for condition in &config.abort_on {
+            println!("  {}", condition);
+        }
+    }
+    println!("{}", "=".repeat(50));
+
+    let (results_tx, results_rx) = mpsc::channel(config.connections * 2);
+    let guardrails = Guardrails::new(config.abort_on.clone(), stop_handle);
+    let aggregator = spawn_aggregator(results_rx, FAILURE_SAMPLES, true, guardrails);
+
+    let test_start = Instant::now();
+    let timeout = Duration::from_secs(config.timeout);
+    let context = Arc::new(WsContext {
+        url: Arc::from(config.url.as_str()),
+        timeout,
+        deadline: tokio::time::Instant::now() + Duration::from_secs(config.duration),
+        drain: timeout.min(grace_period),
+        correlate_by_id: config.message.contains("{{correlationId}}"),
+        transport,
+        stop,
+        config,
+    });
+
+    let handles: Vec<JoinHandle<ConnectionOutcome>> = (0..context.config.connections)
+        .map(|connection| {
+            let delay = Duration::from_secs_f64(
+                context.config.ramp_up * connection as f64 / context.config.connections as f64
+            );
+            tokio::spawn(run_connection(context.clone(), connection + 1, delay, results_tx.clone()))
+        })
+        .collect();
+    drop(results_tx);
+
+    let mut connect_stats = TestStats::new();
+    let mut connect_failures = Vec::new();
+    let mut dropped = 0;
+    for handle in handles {
+        match handle.await {
+            Ok(outcome) => {
+                if let Some(connect) = outcome.connect {
+                    connect_stats.add_result(&connect);
+                    if !connect.success && connect_failures.len() < FAILURE_SAMPLES {
+                        connect_failures.push(connect);
+                    }
+                }
+                dropped += usize::from(outcome.dropped);
+            }
+            Err(e) => eprintln!("Ошибка в задаче: {}", e),
+        }
+    }
+    connect_stats.calculate_final();
+    let report = aggregator.await?;
+
+    println!("\n{}", "=".repeat(50));
+    println!("🎯 ИТОГИ ТЕСТА:");
+    print_stop_notice(&context.stop);
+
+    println!("\n🔌 ПОДКЛЮЧЕНИЯ (время установки соединения):");
+    connect_stats.print_summary();
+    println!("\n💔 Разрывы соединений: {}", dropped);
+
+    println!("\n📨 СООБЩЕНИЯ (время до ответа):");
+    report.stats.print_summary();
+
+    let total_duration = test_start.elapsed();
+    println!("\n⏰ Общее время теста: {:.2} секунд", total_duration.as_secs_f32());
+    println!("📊 Фактически сообщений в секунду: {:.1}",
+        report.stats.total_requests as f32 / total_duration.as_secs_f32());
+
+    if !connect_failures.is_empty() {
+        println!("\n🔍 Неудачные подключения (до {}):", FAILURE_SAMPLES);
+        for failure in &connect_failures {
+            println!("  Соединение {}: {}", failure.user_id, failure.error.as_deref().unwrap_or("Unknown"));
+        }
+    }
+    report.print_failures();
+    Ok(())
+}
+
+async fn run_connection(
+    context: Arc<WsContext>,
+    connection: usize,
+    delay: Duration,
+    results: mpsc::Sender<RequestResult>,
+) -> ConnectionOutcome {
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => {}
+        _ = context.stop.stopped() => {
+            return ConnectionOutcome { connect: None, dropped: false };
+        }
+    }
+
+    let connect_start = Instant::now();
+    let stream = match connect(&context, connection).await {
+        Ok(stream) => stream,
+        Err(error) => {
+            let connect = result(&context, connection, connect_start.elapsed(), Err(error));
+            if context.config.verbose {
+                println!("🔌 {} ❌ {}", connection, connect.error.as_deref().unwrap_or_default());
+            }
+            return ConnectionOutcome { connect: Some(connect), dropped: false };
+        }
+    };
+    let connect = result(&context, connection, connect_start.elapsed(), Ok(()));
+    let (mut write, mut read) = stream.split();
+
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / context.config.rate));
+    let mut expire_ticker = tokio::time::interval(EXPIRE_CHECK);
+    let mut pending: VecDeque<PendingMessage> = VecDeque::new();
+    let mut sequence = 0;
+    let mut dropped = None;
+
+    // Отправка сообщений до конца теста
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(context.deadline) => break,
+            _ = context.stop.stopped() => break,
+            _ = ticker.tick() => {
+                sequence += 1;
+                let id = format!("{}-{}", connection, sequence);
+                let message = render_message(&context.config.message, &id, connection);
+                if let Err(e) = write.send(Message::Text(message)).await {
+                    dropped = Some(e.to_string());
+                    break;
+                }
+                pending.push_back(PendingMessage { id, sent: Instant::now() });
+            }
+            _ = expire_ticker.tick() => {
+                if !expire(&context, connection, &mut pending, &results).await {
+                    break;
+                }
+            }
+            message = read.next() => {
+                if let Err(reason) = receive(&context, connection, message, &mut pending, &results).await {
+                    dropped = Some(reason);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Ожидание ответов на уже отправленные сообщения
+    if dropped.is_none() && !pending.is_empty() {
+        let drain_deadline = tokio::time::Instant::now() + context.drain;
+        while !pending.is_empty() {
+            tokio::select! {
+                _ = tokio::time::sleep_until(drain_deadline) => break,
+                message = read.next() => {
+                    if let Err(reason) = receive(&context, connection, message, &mut pending, &results).await {
+                        dropped = Some(reason);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    // Оставшиеся без ответа сообщения: при разрыве - ошибка соединения, иначе таймаут
+    let (class, error) = match &dropped {
+        Some(reason) => (ErrorClass::Connect, format!("Соединение разорвано: {}", reason)),
+        None => (ErrorClass::Timeout, "Нет ответа до конца теста".to_string()),
+    };
+    for message in pending.drain(..) {
+        let failed = result(&context, connection, message.sent.elapsed(), Err((class, error.clone())));
+        if results.send(failed).await.is_err() {
+            break;
+        }
+    }
+
+    if context.config.verbose && let Some(reason) = &dropped {
+        println!("🔌 {} 💔 {}", connection, reason);
+    }
+    if dropped.is_none() {
+        let _ = write.send(Message::Close(None)).await;
+    }
+
+    ConnectionOutcome { connect: Some(connect), dropped: dropped.is_some() }
+}
+
+type WsStream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn transport(config: &WsConfig) -> Result<Transport, String> {
+    let mut url = Url::parse(&config.url).map_err(|e| format!("Некорректный URL: {}", e))?;
+    if !matches!(url.scheme(), "ws" | "wss") {
+        return Err(format!("Ожидается URL ws:// или wss://: {}", config.url));
+    }
+    crate::dns::check_port(&config.client, &url)?;
+    let host = match url.host() {
+        Some(Host::Domain(domain)) => domain.to_string(),
+        Some(Host::Ipv4(ip)) => ip.to_string(),
+        Some(Host::Ipv6(ip)) => ip.to_string(),
+        None => return Err(format!("В URL нет хоста: {}", config.url)),
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+    if let Some(server_name) = &config.client.tls.tls_server_name {
+        url.set_host(Some(server_name))
+            .map_err(|e| format!("Некорректное имя сервера {}: {}", server_name, e))?;
+    }
+
+    let auth = match config.client.auth.config() {
+        Some(auth) => {
+            let token_client = client::build_client(&config.client)?.for_worker(0).clone();
+            Some(AuthProviders::new(token_client).get(&auth).await?)
+        }
+        None => None,
+    };
+
+    Ok(Transport {
+        tcp: TcpConnector::new(&config.client)?,
+        tls: client::tls_connector(&config.client.tls)?,
+        request_url: url.to_string(),
+        host,
+        port,
+        connect_timeout: config.client.connect_timeout.map_or(Duration::from_secs(config.timeout), Duration::from_secs),
+        auth,
+    })
+}
+
+async fn connect(context: &WsContext, connection: usize) -> Result<WsStream, (ErrorClass, String)> {
+    let config = &context.config;
+    let transport = &context.transport;
+    let mut request = transport.request_url.as_str().into_client_request()
+        .map_err(|e| (ErrorClass::Other, format!("Некорректный URL: {}", e)))?;
+    for header in &config.headers {
+        let (name, value) = header.split_once(':')
+            .ok_or_else(|| (ErrorClass::Other, format!("Некорректный заголовок: {}", header)))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|e| (ErrorClass::Other, format!("Некорректное имя заголовка {}: {}", name, e)))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|e| (ErrorClass::Other, format!("Некорректное значение заголовка {}: {}", name, e)))?;
+        request.headers_mut().insert(name, value);
+    }
+    if let Some(auth) = &transport.auth {
+        let value = HeaderValue::from_bytes(auth.header().as_bytes())
+            .map_err(|e| (ErrorClass::Other, format!("Некорректное значение для Authorization: {}", e)))?;
+        request.headers_mut().insert("authorization", value);
+    }
+
+    let connecting = async {
+        let stream = tokio::time::timeout(
+            transport.connect_timeout,
+            transport.tcp.connect(&transport.host, transport.port, connection - 1),
+        )
+        .await
+        .map_err(|_| (ErrorClass::Timeout, "Таймаут подключения".to_string()))?
+        .map_err(|e| (ErrorClass::Connect, format!("Ошибка подключения: {}", e)))?;
+        let connector = Connector::NativeTls(transport.tls.clone());
+        tokio_tungstenite::client_async_tls_with_config(request, stream, None, Some(connector)).await
+            .map_err(|e| (classify_error(&e), e.to_string()))
+    };
+    match tokio::time::timeout(context.timeout, connecting).await {
+        Ok(Ok((stream, _))) => Ok(stream),
+        Ok(Err(error)) => Err(error),
+        Err(_) => Err((ErrorClass::Timeout, "Таймаут подключения".to_string())),
+    }
+}
+
+fn classify_error(error: &WsError) -> ErrorClass {
+    match error {
+        WsError::Http(_) => ErrorClass::Http,
+        WsError::Tls(_) => ErrorClass::Tls,
+        WsError::Io(_) => ErrorClass::Connect,
+        _ => ErrorClass::Other,
+    }
+}
+
+// Обрабатывает входящее сообщение; Err - соединение закрыто
+async fn receive(
+    context: &WsContext,
+    connection: usize,
+    message: Option<Result<Message, WsError>>,
+    pending: &mut VecDeque<PendingMessage>,
+    results: &mpsc::Sender<RequestResult>,
+) -> Result<(), String> {
+    let text = match message {
+        Some(Ok(Message::Text(text))) => text,
+        Some(Ok(Message::Binary(data))) => String::from_utf8_lossy(&data).into_owned(),
+        Some(Ok(Message::Close(frame))) => {
+            return Err(match frame {
+                Some(frame) => format!("сервер закрыл соединение ({} {})", frame.code, frame.reason),
+                None => "сервер закрыл соединение".to_string(),
+            });
+        }
+        Some(Ok(_)) => return Ok(()),
+        Some(Err(e)) => return Err(e.to_string()),
+        None => return Err("соединение закрыто".to_string()),
+    };
+
+    let position = if context.correlate_by_id {
+        correlation_id(&text, &context.config.correlation_field)
+            .and_then(|id| pending.iter().position(|message| message.id == id))
+    } else if pending.is_empty() {
+        None
+    } else {
+        Some(0)
+    };
+    // Сообщения, не являющиеся ответами (например, рассылки сервера), не учитываются
+    let Some(message) = position.and_then(|position| pending.remove(position)) else {
+        return Ok(());
+    };
+
+    let round_trip = message.sent.elapsed();
+    if context.config.verbose {
+        println!("👤 {} {} ✅ {:.2}ms", connection, message.id, round_trip.as_millis());
+    }
+    // Агрегатор завершился только вместе с тестом, результат можно не отправлять
+    let _ = results.send(result(context, connection, round_trip, Ok(()))).await;
+    Ok(())
+}
+
+// Отмечает сообщения без ответа дольше таймаута; false - агрегатор уже закрыт
+async fn expire(
+    context: &WsContext,
+    connection: usize,
+    pending: &mut VecDeque<PendingMessage>,
+    results: &mpsc::Sender<RequestResult>,
+) -> bool {
+    // Сообщения упорядочены по времени отправки
+    while let Some(message) = pending.front() {
+        if message.sent.elapsed() < context.timeout {
+            break;
+        }
+        let message = pending.pop_front().expect("очередь не пуста");
+        if context.config.verbose {
+            println!("👤 {} {} ❌ Нет ответа", connection, message.id);
+        }
+        let timed_out = result(context, connection, message.sent.elapsed(),
+            Err((ErrorClass::Timeout, format!("Нет ответа на {} за {} с", message.id, context.timeout.as_secs()))));
+        if results.send(timed_out).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+fn correlation_id(text: &str, field: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(text).ok()?;
+    match value.get(field)? {
+        serde_json::Value::String(id) => Some(id.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn render_message(template: &str, id: &str, connection: usize) -> String {
+    template
+        .replace("{{correlationId}}", id)
+        .replace("{{userId}}", &connection.to_string())
+        .replace("{{timestamp}}", &Utc::now().to_rfc3339())
+        .replace("{{uuid}}", &uuid::Uuid::new_v4().to_string())
+}
+
+fn result(
+    context: &WsContext,
+    connection: usize,
+    duration: Duration,
+    outcome: Result<(), (ErrorClass, String)>,
+) -> RequestResult {
+    let (success, error_class, error) = match outcome {
+        Ok(()) => (true, None, None),
+        Err((class, error)) => (false, Some(class), Some(error)),
+    };
+    RequestResult {
+        user_id: connection,
+        success,
+        duration,
+        status_code: None,
+        error,
+        error_class,
+        url: context.url.clone(),
+        redirects: 0,
+        final_url: None,
+        stream: None,
+    }
+}